@@ -22,3 +22,33 @@ impl<'de> Deserialize<'de> for Program {
         Ok(lang)
     }
 }
+
+#[cfg(feature = "with-schemars")]
+impl schemars::JsonSchema for Program {
+    fn schema_name() -> String {
+        "Program".to_string()
+    }
+
+    fn json_schema(_: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        use schemars::schema::{InstanceType, Metadata, SchemaObject, StringValidation};
+
+        SchemaObject {
+            instance_type: Some(InstanceType::String.into()),
+            string: Some(Box::new(StringValidation {
+                pattern: Some(r"^[\sA-Za-z0-9_+\-*!()]+$".to_string()),
+                ..Default::default()
+            })),
+            metadata: Some(Box::new(Metadata {
+                description: Some(
+                    "A Boolean expression over item names, where `+` is union, `*` is \
+                     intersection, `-` is difference and `!` is negation."
+                        .to_string(),
+                ),
+                examples: vec!["int * (box1 - box2)".into()],
+                ..Default::default()
+            })),
+            ..Default::default()
+        }
+        .into()
+    }
+}
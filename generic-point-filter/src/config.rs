@@ -1,36 +1,63 @@
 use nalgebra::Isometry3;
 use noisy_float::types::{N32, R32};
-use range_point_filter::FilterProgram;
+use range_point_filter::{units, FilterProgram};
 use serde::{Deserialize, Serialize};
-use static_point_filter::StaticPointFilter;
+use static_point_filter::{RangeImageFilter, StaticPointFilter};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "with-schemars", derive(schemars::JsonSchema))]
 pub struct Config {
     pub ground_filter: Option<GroundFilter>,
     pub lidar_filter: Option<LidarFilter>,
     pub range_filter: Option<FilterProgram>,
     pub background_filter: Option<StaticPointFilter>,
+    /// The background model over the range image, which is an
+    /// alternative to the voxel-based background filter.
+    #[serde(default)]
+    pub range_image_filter: Option<RangeImageFilter>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "with-schemars", derive(schemars::JsonSchema))]
 pub struct GroundFilter {
     /// The transform from world to ground coordinate.
     #[serde(with = "newslab_serde_nalgebra::isometry3_as_euler_angles")]
+    #[cfg_attr(feature = "with-schemars", schemars(with = "TransformSchema"))]
     pub transform: Isometry3<f32>,
 
     /// The maximum effective distance from the ground center.
+    #[serde(deserialize_with = "units::length")]
+    #[cfg_attr(feature = "with-schemars", schemars(with = "units::Meters"))]
     pub max_distance: N32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "with-schemars", derive(schemars::JsonSchema))]
 pub struct LidarFilter {
     /// The transform from world to lidar coordinate.
     #[serde(with = "newslab_serde_nalgebra::isometry3_as_euler_angles")]
+    #[cfg_attr(feature = "with-schemars", schemars(with = "TransformSchema"))]
     pub transform: Isometry3<f32>,
 
     /// The minimum effective distance from the LiDAR center.
+    #[serde(deserialize_with = "units::length")]
+    #[cfg_attr(feature = "with-schemars", schemars(with = "units::Meters"))]
     pub min_distance: R32,
 
     /// The maximum effective distance from the LiDAR center.
+    #[serde(default, deserialize_with = "units::length")]
+    #[cfg_attr(feature = "with-schemars", schemars(with = "Option<units::Meters>"))]
     pub max_distance: Option<R32>,
 }
+
+/// The schema of transforms in the Euler angle format.
+#[cfg(feature = "with-schemars")]
+#[derive(schemars::JsonSchema)]
+#[schemars(rename = "Transform")]
+#[allow(dead_code)]
+struct TransformSchema {
+    translation: [f64; 3],
+
+    /// The roll, pitch and yaw angles with units, such as `"90deg"`.
+    rotation: [String; 3],
+}
@@ -1,11 +1,17 @@
 use crate::config::{self, Config};
-use anyhow::Result;
+use anyhow::{bail, Result};
+use arc_swap::ArcSwap;
 use nalgebra::{coordinates::XYZ, Isometry3, Point3, Scalar, Vector2};
 use num::Float;
-use range_point_filter::FilterProgram;
+use range_point_filter::{Aabb, FilterProgram};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
-use static_point_filter::StaticPointFilter;
-use std::ops::{Bound, RangeBounds, RangeInclusive};
+use static_point_filter::{BackgroundVoxel, Mode, RangeImageFilter, StaticPointFilter};
+use std::{
+    fmt::{self, Display, Formatter},
+    io::{Read, Write},
+    ops::{Bound, RangeBounds, RangeInclusive},
+    sync::Arc,
+};
 
 pub type Pt32 = Pt<f32>;
 pub type Pt64 = Pt<f64>;
@@ -40,106 +46,448 @@ where
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub struct Filter {
+    stages: ArcSwap<Stages>,
+}
+
+/// The filter stages built from a config, which are replaced as a
+/// whole on reconfiguration.
+#[derive(Debug)]
+struct Stages {
     ground_filter: Option<GroundFilter>,
     lidar_filter: Option<LidarFilter>,
     range_filter: Option<FilterProgram>,
-    background_filter: Option<StaticPointFilter>,
+    /// The bounds of the range filter for early rejection, and the
+    /// bounds covering all timestamps.
+    range_bounds: Option<Aabb>,
+    range_bounds_over_time: Option<Aabb>,
+    /// The background models, which are shared across stages built
+    /// from configs with the same background filters.
+    background_filter: Option<Arc<StaticPointFilter>>,
+    range_image_filter: Option<Arc<RangeImageFilter>>,
     config: Config,
 }
 
-impl Filter {
-    pub fn new(config: &Config) -> Self {
+impl Stages {
+    fn new(
+        config: &Config,
+        background_filter: Option<Arc<StaticPointFilter>>,
+        range_image_filter: Option<Arc<RangeImageFilter>>,
+    ) -> Self {
         let Config {
             ground_filter,
             lidar_filter,
             range_filter,
-            background_filter,
+            ..
         } = config;
         let lidar_filter = lidar_filter.as_ref().map(LidarFilter::new);
         let ground_filter = ground_filter.as_ref().map(GroundFilter::new);
+        let range_bounds = range_filter.as_ref().and_then(|filter| filter.bounds());
+        let range_bounds_over_time = range_filter
+            .as_ref()
+            .and_then(|filter| filter.bounds_over_time());
 
         Self {
             ground_filter,
             lidar_filter,
             range_filter: range_filter.clone(),
-            background_filter: background_filter.clone(),
+            range_bounds,
+            range_bounds_over_time,
+            background_filter,
+            range_image_filter,
             config: config.clone(),
         }
     }
 
-    pub fn contains<T>(&self, point: &Pt<T>) -> bool
+    fn step(&self) {
+        // Update background point filter statistics
+        if let Some(filter) = &self.background_filter {
+            filter.step();
+        }
+        if let Some(filter) = &self.range_image_filter {
+            filter.step();
+        }
+    }
+
+    /// Finds the first stage which rejects the point. The point
+    /// occurrence is recorded in background statistics if `observe`
+    /// is true.
+    fn rejecting_stage<T>(&self, point: &Pt<T>, time: Option<f64>, observe: bool) -> Option<Stage>
     where
         T: Float,
     {
         macro_rules! ensure {
-            ($cond:expr) => {
+            ($cond:expr, $stage:expr) => {
                 if !$cond {
-                    return false;
+                    return Some($stage);
                 }
             };
         }
 
-        let Pt {
-            xyz: [x, y, z],
-            intensity,
-        } = *point;
-        let p32: Point3<f32> = [
-            num::cast(x).unwrap(),
-            num::cast(y).unwrap(),
-            num::cast(z).unwrap(),
-        ]
-        .into();
-        let p64: Point3<f64> = [
-            num::cast(x).unwrap(),
-            num::cast(y).unwrap(),
-            num::cast(z).unwrap(),
-        ]
-        .into();
+        let (p32, p64, intensity) = convert_point(point);
 
         // Filter points by the distance to the lidar.
         if let Some(filter) = &self.lidar_filter {
-            ensure!(filter.contains(&p32));
+            ensure!(filter.contains(&p32), Stage::Lidar);
         }
 
         // Ground filter
         if let Some(filter) = &self.ground_filter {
-            ensure!(filter.contains(&p32));
+            ensure!(filter.contains(&p32), Stage::Ground);
+        }
+
+        // Reject points out of the range filter bounds before
+        // evaluating items.
+        let bounds = match time {
+            Some(_) => &self.range_bounds_over_time,
+            None => &self.range_bounds,
+        };
+        if let Some(bounds) = bounds {
+            ensure!(bounds.contains(&p64), Stage::Range);
         }
 
         // Apply range filter
         if let Some(filter) = &self.range_filter {
-            ensure!(filter.contains(&p64, intensity.map(|v| num::cast(v).unwrap())));
+            let yes = match time {
+                Some(time) => filter.contains_at(&p64, intensity, time),
+                None => filter.contains(&p64, intensity),
+            };
+            ensure!(yes, Stage::Range);
         }
 
         // Apply statistical background point filter
         if let Some(filter) = &self.background_filter {
-            ensure!(!filter.check_is_background(&p64));
+            let is_background = if observe {
+                filter.check_is_background(&p64)
+            } else {
+                filter.is_background(&p64)
+            };
+            ensure!(!is_background, Stage::Background);
+        }
+
+        // Apply range image background filter
+        if let Some(filter) = &self.range_image_filter {
+            let is_background = if observe {
+                filter.check_is_background(&p64)
+            } else {
+                filter.is_background(&p64)
+            };
+            ensure!(!is_background, Stage::Background);
         }
 
-        true
+        None
     }
+}
 
-    pub fn step(&self) {
-        // Update background point filter statistics
-        if let Some(filter) = &self.background_filter {
-            filter.step();
+impl Clone for Filter {
+    fn clone(&self) -> Self {
+        let stages = self.stages();
+        let background_filter = stages
+            .background_filter
+            .as_ref()
+            .map(|filter| Arc::new((**filter).clone()));
+        let range_image_filter = stages
+            .range_image_filter
+            .as_ref()
+            .map(|filter| Arc::new((**filter).clone()));
+
+        Self {
+            stages: ArcSwap::from_pointee(Stages::new(
+                &stages.config,
+                background_filter,
+                range_image_filter,
+            )),
+        }
+    }
+}
+
+impl Filter {
+    pub fn new(config: &Config) -> Self {
+        let background_filter = config.background_filter.clone().map(Arc::new);
+        let range_image_filter = config.range_image_filter.clone().map(Arc::new);
+
+        Self {
+            stages: ArcSwap::from_pointee(Stages::new(
+                config,
+                background_filter,
+                range_image_filter,
+            )),
+        }
+    }
+
+    /// Rebuilds the lidar, ground and range stages from the config,
+    /// and replaces them at once. Points are checked against either
+    /// the old or the new stages, but never a mix of both.
+    ///
+    /// The learned background statistics are kept if the background
+    /// filter config is unchanged, and migrated to the new voxel
+    /// grid if it is aligned with the old one and the model is
+    /// unchanged. The migrated filter shares the statistics with the
+    /// old one, so mode and threshold changes are applied in place,
+    /// and points observed meanwhile are kept. Otherwise the
+    /// reconfiguration is rejected and the
+    /// filter is left unchanged. The range image model is kept if its
+    /// config is unchanged, or learned from scratch otherwise.
+    pub fn reconfigure(&self, config: &Config) -> Result<()> {
+        let mut old = self.stages.load_full();
+        loop {
+            let new = Arc::new(Self::rebuild(&old, config)?);
+
+            // Retry if the stages are replaced by a concurrent
+            // reconfiguration in the meantime.
+            let prev = self.stages.compare_and_swap(&old, new);
+            if Arc::ptr_eq(&prev, &old) {
+                return Ok(());
+            }
+            old = arc_swap::Guard::into_inner(prev);
         }
     }
 
+    /// Builds the stages for the config, which take over the models
+    /// of the old stages.
+    fn rebuild(old: &Stages, config: &Config) -> Result<Stages> {
+        let background_filter = match (&old.background_filter, &config.background_filter) {
+            (_, None) => None,
+            (None, Some(new)) => Some(Arc::new(new.clone())),
+            (Some(old), Some(new)) if old.config() == new.config() => Some(old.clone()),
+            (Some(old), Some(new)) => {
+                let Some(migrated) = old.migrate(new.config()) else {
+                    bail!(
+                        "unable to migrate the background statistics to a voxel grid \
+                         with a different voxel size, an unaligned range or a different model"
+                    );
+                };
+                Some(Arc::new(migrated))
+            }
+        };
+
+        let range_image_filter = match (&old.range_image_filter, &config.range_image_filter) {
+            (Some(old), Some(new)) if old.config() == new.config() => Some(old.clone()),
+            (_, new) => new.clone().map(Arc::new),
+        };
+
+        Ok(Stages::new(config, background_filter, range_image_filter))
+    }
+
+    /// Takes a snapshot of the current stages.
+    fn stages(&self) -> Arc<Stages> {
+        self.stages.load_full()
+    }
+
+    pub fn contains<T>(&self, point: &Pt<T>) -> bool
+    where
+        T: Float,
+    {
+        self.contains_impl(point, None)
+    }
+
+    /// Checks the point captured at the timestamp in seconds. The
+    /// timestamp enables scheduled and moving items in the range
+    /// filter.
+    pub fn contains_at<T>(&self, point: &Pt<T>, time: f64) -> bool
+    where
+        T: Float,
+    {
+        self.contains_impl(point, Some(time))
+    }
+
+    fn contains_impl<T>(&self, point: &Pt<T>, time: Option<f64>) -> bool
+    where
+        T: Float,
+    {
+        self.stages
+            .load()
+            .rejecting_stage(point, time, true)
+            .is_none()
+    }
+
+    /// Explains which stage rejects the point without updating the
+    /// background statistics.
+    pub fn explain<T>(&self, point: &Pt<T>) -> Explanation
+    where
+        T: Float,
+    {
+        self.explain_impl(point, None)
+    }
+
+    /// Explains which stage rejects the point captured at the
+    /// timestamp in seconds.
+    pub fn explain_at<T>(&self, point: &Pt<T>, time: f64) -> Explanation
+    where
+        T: Float,
+    {
+        self.explain_impl(point, Some(time))
+    }
+
+    fn explain_impl<T>(&self, point: &Pt<T>, time: Option<f64>) -> Explanation
+    where
+        T: Float,
+    {
+        let stages = self.stages.load();
+        let rejected_by = stages.rejecting_stage(point, time, false);
+        let range = stages.range_filter.as_ref().map(|filter| {
+            let (_, p64, intensity) = convert_point(point);
+            match time {
+                Some(time) => filter.explain_at(&p64, intensity, time),
+                None => filter.explain(&p64, intensity),
+            }
+        });
+
+        Explanation { rejected_by, range }
+    }
+
+    pub fn step(&self) {
+        self.stages().step();
+    }
+
+    /// Filters a frame. The frame is checked against the same stages
+    /// even if the filter is reconfigured meanwhile.
     pub fn filter_frame<T, I>(&self, frame: I) -> Result<Vec<Pt<T>>>
     where
         T: Float,
         I: IntoIterator<Item = Pt<T>>,
     {
-        let points: Vec<_> = frame.into_iter().filter(|p| self.contains(p)).collect();
-        self.step();
+        let stages = self.stages();
+        let points: Vec<_> = frame
+            .into_iter()
+            .filter(|p| stages.rejecting_stage(p, None, true).is_none())
+            .collect();
+        stages.step();
+        Ok(points)
+    }
+
+    /// Filters a frame captured at the timestamp in seconds.
+    pub fn filter_frame_at<T, I>(&self, frame: I, time: f64) -> Result<Vec<Pt<T>>>
+    where
+        T: Float,
+        I: IntoIterator<Item = Pt<T>>,
+    {
+        let stages = self.stages();
+        let points: Vec<_> = frame
+            .into_iter()
+            .filter(|p| stages.rejecting_stage(p, Some(time), true).is_none())
+            .collect();
+        stages.step();
         Ok(points)
     }
 
-    pub fn config(&self) -> &Config {
-        &self.config
+    /// Writes the learned background statistics to a binary
+    /// snapshot.
+    pub fn save_background_state<W: Write>(&self, writer: W) -> Result<()> {
+        let Some(filter) = &self.stages().background_filter else {
+            bail!("the background filter is not configured");
+        };
+        filter.save_state(writer)
+    }
+
+    /// Restores the background statistics from a snapshot saved by
+    /// [save_background_state](Self::save_background_state).
+    pub fn load_background_state<R: Read>(&self, reader: R) -> Result<()> {
+        let Some(filter) = &self.stages().background_filter else {
+            bail!("the background filter is not configured");
+        };
+        filter.load_state(reader)
+    }
+
+    /// Lists voxels of the background filter which are occupied in
+    /// at least the `threshold` ratio of frames.
+    pub fn export_background(&self, threshold: f64) -> Result<Vec<BackgroundVoxel>> {
+        let Some(filter) = &self.stages().background_filter else {
+            bail!("the background filter is not configured");
+        };
+        Ok(filter.export_background(threshold))
+    }
+
+    /// Switches the operating mode of the background filter, for
+    /// example, to freeze the background learned in a calibration
+    /// window.
+    pub fn set_background_mode(&self, mode: Mode) -> Result<()> {
+        let Some(filter) = &self.stages().background_filter else {
+            bail!("the background filter is not configured");
+        };
+        filter.set_mode(mode);
+        Ok(())
+    }
+
+    /// The config of the current stages.
+    pub fn config(&self) -> Config {
+        self.stages().config.clone()
+    }
+}
+
+fn convert_point<T>(point: &Pt<T>) -> (Point3<f32>, Point3<f64>, Option<f64>)
+where
+    T: Float,
+{
+    let Pt {
+        xyz: [x, y, z],
+        intensity,
+    } = *point;
+    let p32: Point3<f32> = [
+        num::cast(x).unwrap(),
+        num::cast(y).unwrap(),
+        num::cast(z).unwrap(),
+    ]
+    .into();
+    let p64: Point3<f64> = [
+        num::cast(x).unwrap(),
+        num::cast(y).unwrap(),
+        num::cast(z).unwrap(),
+    ]
+    .into();
+    let intensity = intensity.map(|v| num::cast(v).unwrap());
+
+    (p32, p64, intensity)
+}
+
+/// The stages of the filter pipeline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Stage {
+    Lidar,
+    Ground,
+    Range,
+    Background,
+}
+
+impl Display for Stage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Stage::Lidar => "lidar filter",
+            Stage::Ground => "ground filter",
+            Stage::Range => "range filter",
+            Stage::Background => "background filter",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// The explanation of the filter decision on a point.
+#[derive(Debug, Clone)]
+pub struct Explanation {
+    /// The first stage which rejects the point, or `None` if the
+    /// point is accepted.
+    pub rejected_by: Option<Stage>,
+
+    /// The trace of the range filter if it is configured.
+    pub range: Option<range_point_filter::explain::Explanation>,
+}
+
+impl Display for Explanation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.rejected_by {
+            Some(stage) => writeln!(f, "rejected by the {stage}")?,
+            None => writeln!(f, "accepted")?,
+        }
+
+        if let Some(range) = &self.range {
+            writeln!(f, "range filter trace:")?;
+            for line in range.to_string().lines() {
+                writeln!(f, "  {line}")?;
+            }
+        }
+
+        Ok(())
     }
 }
 
@@ -148,7 +496,7 @@ impl Serialize for Filter {
     where
         S: Serializer,
     {
-        self.config.serialize(serializer)
+        self.stages().config.serialize(serializer)
     }
 }
 
@@ -1,7 +1,11 @@
 pub mod config;
 mod filter;
+#[cfg(feature = "with-notify")]
+mod watch;
 #[cfg(feature = "with-kiss3d")]
 mod with_kiss3d;
 
 pub use config::Config;
-pub use filter::{Filter, Pt, Pt32, Pt64};
+pub use filter::{Explanation, Filter, Pt, Pt32, Pt64, Stage};
+#[cfg(feature = "with-notify")]
+pub use watch::ConfigWatcher;
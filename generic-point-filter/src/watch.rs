@@ -0,0 +1,72 @@
+use crate::{Config, Filter};
+use anyhow::{Context, Result};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+/// Reconfigures a filter whenever its config file changes. Watching
+/// stops when it is dropped.
+pub struct ConfigWatcher {
+    _watcher: RecommendedWatcher,
+    path: PathBuf,
+}
+
+impl ConfigWatcher {
+    /// Watches the JSON5 config file at the path. The callback
+    /// receives the result of each reload. A config which fails to
+    /// load or to be applied leaves the filter unchanged.
+    pub fn new<P, F>(filter: Arc<Filter>, path: P, mut on_reload: F) -> Result<Self>
+    where
+        P: AsRef<Path>,
+        F: FnMut(Result<()>) + Send + 'static,
+    {
+        let path = path.as_ref().to_path_buf();
+        let file_name = path.file_name().map(|name| name.to_os_string());
+
+        // Watch the parent directory since editors often replace the
+        // file rather than write to it.
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+
+        let config_path = path.clone();
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+                let event = match event {
+                    Ok(event) => event,
+                    Err(err) => return on_reload(Err(err.into())),
+                };
+                if !(event.kind.is_create() || event.kind.is_modify()) {
+                    return;
+                }
+                let is_config = event.paths.iter().any(|changed| {
+                    changed.file_name().map(|name| name.to_os_string()) == file_name
+                });
+                if is_config {
+                    on_reload(reload(&filter, &config_path));
+                }
+            })?;
+        watcher.watch(&dir, RecursiveMode::NonRecursive)?;
+
+        Ok(Self {
+            _watcher: watcher,
+            path,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+fn reload(filter: &Filter, path: &Path) -> Result<()> {
+    let text =
+        fs::read_to_string(path).with_context(|| format!("Unable to read {}", path.display()))?;
+    let config: Config =
+        json5::from_str(&text).with_context(|| format!("Unable to parse {}", path.display()))?;
+    filter.reconfigure(&config)
+}
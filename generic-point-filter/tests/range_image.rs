@@ -0,0 +1,37 @@
+use anyhow::Result;
+use generic_point_filter::{Config, Filter, Pt64, Stage};
+use nalgebra::Point3;
+
+#[test]
+fn range_image_test() -> Result<()> {
+    let config: Config = json5::from_str(
+        r#"
+{
+    range_image_filter: {
+        sensor_pose: { position: [0, 0, 1] },
+        azimuth_resolution_degrees: 1,
+        elevation_resolution_degrees: 2,
+        elevation_bound: [-15, 15],
+        range_resolution: 0.5,
+        margin: 1,
+        background_threshold: 0.8,
+    },
+}
+"#,
+    )?;
+    let filter = Filter::new(&config);
+    let wall = Pt64::from(Point3::new(20.0, 0.0, 1.0));
+    let person = Pt64::from(Point3::new(10.0, 0.0, 1.0));
+
+    for _ in 0..10 {
+        filter.filter_frame([wall])?;
+    }
+    assert_eq!(filter.explain(&wall).rejected_by, Some(Stage::Background));
+    assert_eq!(filter.explain(&person).rejected_by, None);
+
+    // The model is kept if its config is unchanged.
+    filter.reconfigure(&config)?;
+    assert_eq!(filter.explain(&wall).rejected_by, Some(Stage::Background));
+
+    Ok(())
+}
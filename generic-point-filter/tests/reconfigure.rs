@@ -0,0 +1,93 @@
+use anyhow::Result;
+use generic_point_filter::{Config, Filter, Pt64, Stage};
+use nalgebra::Point3;
+use static_point_filter::Mode;
+
+fn config(box_width: f64, x_bound: [f64; 2], voxel_size: f64) -> Result<Config> {
+    let [x_min, x_max] = x_bound;
+    let text = format!(
+        r#"
+{{
+    "range_filter": {{
+        "program": "box",
+        "items": {{
+            "box": {{
+                "type": "box",
+                "xy": [0, 0],
+                "wh": [{box_width}, {box_width}],
+                "azimuth_degrees": 0
+            }}
+        }}
+    }},
+    "background_filter": {{
+        "range": {{
+            "x_bound": [{x_min}, {x_max}],
+            "y_bound": [-10, 10],
+            "z_bound": [-10, 10]
+        }},
+        "voxel_size": {{
+            "x_size": {voxel_size},
+            "y_size": {voxel_size},
+            "z_size": {voxel_size}
+        }},
+        "background_threshold": 0.8
+    }}
+}}
+"#
+    );
+    Ok(json5::from_str(&text)?)
+}
+
+#[test]
+fn reconfigure_test() -> Result<()> {
+    let filter = Filter::new(&config(10.0, [-10.0, 10.0], 1.0)?);
+    let wall = Pt64::from(Point3::new(2.5, 0.5, 0.5));
+    let person = Pt64::from(Point3::new(-2.5, 0.5, 0.5));
+
+    // Learn the static wall.
+    for _ in 0..10 {
+        filter.filter_frame([wall])?;
+    }
+    assert_eq!(filter.explain(&wall).rejected_by, Some(Stage::Background));
+    assert_eq!(filter.explain(&person).rejected_by, None);
+
+    // Changing the range filter keeps the background model.
+    filter.reconfigure(&config(4.0, [-10.0, 10.0], 1.0)?)?;
+    assert_eq!(filter.explain(&wall).rejected_by, Some(Stage::Range));
+    filter.reconfigure(&config(8.0, [-10.0, 10.0], 1.0)?)?;
+    assert_eq!(filter.explain(&wall).rejected_by, Some(Stage::Background));
+
+    // An aligned range is migrated.
+    filter.reconfigure(&config(8.0, [-3.0, 9.0], 1.0)?)?;
+    assert_eq!(filter.explain(&wall).rejected_by, Some(Stage::Background));
+    assert_eq!(filter.explain(&person).rejected_by, None);
+
+    // A different voxel size is rejected and leaves the filter
+    // unchanged.
+    let before = serde_json::to_value(filter.config())?;
+    assert!(filter.reconfigure(&config(6.0, [-3.0, 9.0], 0.5)?).is_err());
+    assert!(filter.reconfigure(&config(6.0, [-3.5, 9.0], 1.0)?).is_err());
+    assert_eq!(serde_json::to_value(filter.config())?, before);
+    assert_eq!(filter.explain(&wall).rejected_by, Some(Stage::Background));
+
+    Ok(())
+}
+
+#[test]
+fn freeze_test() -> Result<()> {
+    let filter = Filter::new(&config(10.0, [-10.0, 10.0], 1.0)?);
+    let wall = Pt64::from(Point3::new(2.5, 0.5, 0.5));
+    let car = Pt64::from(Point3::new(-2.5, 0.5, 0.5));
+
+    for _ in 0..10 {
+        filter.filter_frame([wall])?;
+    }
+    filter.set_background_mode(Mode::Frozen)?;
+
+    // The parked car is never absorbed into the frozen background.
+    for _ in 0..100 {
+        assert_eq!(filter.filter_frame([wall, car])?, vec![car]);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,28 @@
+#![cfg(feature = "with-schemars")]
+
+use anyhow::Result;
+use generic_point_filter::Config;
+use std::{env, fs};
+
+const SCHEMA_PATH: &str = "config.schema.json";
+
+/// Checks that the exported schema is in sync with the config types.
+/// Run with `UPDATE_SCHEMA=1` to regenerate it.
+#[test]
+fn config_schema_test() -> Result<()> {
+    let schema = schemars::schema_for!(Config);
+    let text = serde_json::to_string_pretty(&schema)? + "\n";
+
+    if env::var_os("UPDATE_SCHEMA").is_some() {
+        fs::write(SCHEMA_PATH, &text)?;
+        return Ok(());
+    }
+
+    let expect = fs::read_to_string(SCHEMA_PATH)?;
+    assert!(
+        text == expect,
+        "{SCHEMA_PATH} is outdated. Run the test with UPDATE_SCHEMA=1 to regenerate it."
+    );
+
+    Ok(())
+}
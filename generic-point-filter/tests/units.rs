@@ -0,0 +1,36 @@
+use anyhow::Result;
+use generic_point_filter::config::LidarFilter;
+
+#[test]
+fn lidar_filter_units_test() -> Result<()> {
+    let text = r#"
+{
+    "transform": {
+        "translation": [0.0, 0.0, 0.0],
+        "rotation": ["0deg", "0deg", "0deg"]
+    },
+    "min_distance": "500mm",
+    "max_distance": "0.05km"
+}
+"#;
+    let filter: LidarFilter = json5::from_str(text)?;
+    assert_eq!(filter.min_distance.raw(), 0.5);
+    assert_eq!(filter.max_distance.map(|d| d.raw()), Some(50.0));
+
+    Ok(())
+}
+
+#[test]
+fn f32_overflow_test() {
+    let text = r#"
+{
+    "transform": {
+        "translation": [0.0, 0.0, 0.0],
+        "rotation": ["0deg", "0deg", "0deg"]
+    },
+    "min_distance": "1e300m"
+}
+"#;
+    let err = json5::from_str::<LidarFilter>(text).unwrap_err();
+    assert!(err.to_string().contains("overflows f32"), "{err}");
+}
@@ -0,0 +1,40 @@
+use anyhow::Result;
+use clap::Parser;
+use range_point_filter::geodetic::{from_geojson, GeoJsonOptions};
+use std::{fs, path::PathBuf};
+
+#[derive(Debug, Clone, Parser)]
+/// Convert GeoJSON regions in WGS84 to range filter items.
+struct Opts {
+    /// Conversion options, including the geodetic origin.
+    #[clap(short = 'c', long)]
+    pub config: PathBuf,
+
+    /// The input GeoJSON file.
+    #[clap(short = 'i', long)]
+    pub input: PathBuf,
+
+    /// The output file. The items are printed to stdout if it is not
+    /// provided.
+    #[clap(short = 'o', long)]
+    pub output: Option<PathBuf>,
+}
+
+fn main() -> Result<()> {
+    let opts = Opts::parse();
+    let options: GeoJsonOptions = {
+        let text = fs::read_to_string(&opts.config)?;
+        json5::from_str(&text)?
+    };
+
+    let text = fs::read_to_string(&opts.input)?;
+    let items = from_geojson(&text, &options)?;
+    let output = serde_json::to_string_pretty(&serde_json::json!({ "items": items }))?;
+
+    match &opts.output {
+        Some(path) => fs::write(path, output)?,
+        None => println!("{output}"),
+    }
+
+    Ok(())
+}
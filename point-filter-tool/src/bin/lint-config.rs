@@ -0,0 +1,41 @@
+use anyhow::{bail, Result};
+use clap::Parser;
+use generic_point_filter::Config;
+use range_point_filter::lint::Severity;
+use std::{fs, path::PathBuf};
+
+#[derive(Debug, Clone, Parser)]
+/// Report problems in the range filter of a configuration file.
+struct Opts {
+    /// Configuration file.
+    #[clap(short = 'c', long)]
+    pub config: PathBuf,
+}
+
+fn main() -> Result<()> {
+    let opts = Opts::parse();
+    let config: Config = {
+        let text = fs::read_to_string(&opts.config)?;
+        json5::from_str(&text)?
+    };
+
+    let Some(program) = &config.range_filter else {
+        println!("no range filter is configured");
+        return Ok(());
+    };
+
+    let findings = program.lint();
+    for finding in &findings {
+        println!("{finding}");
+    }
+
+    let num_errors = findings
+        .iter()
+        .filter(|finding| finding.severity == Severity::Error)
+        .count();
+    if num_errors > 0 {
+        bail!("found {num_errors} error(s)");
+    }
+
+    Ok(())
+}
@@ -13,6 +13,8 @@ use nalgebra as na32;
 pub struct GuiMsg {
     pub foreground_points: Vec<na32::Point3<f32>>,
     pub background_points: Vec<na32::Point3<f32>>,
+    /// The centers and occupancy ratios of background voxels.
+    pub background_voxels: Vec<(na32::Point3<f32>, f32)>,
 }
 
 #[derive(Debug)]
@@ -23,6 +25,7 @@ pub struct Gui {
     ground_tf: Option<na30::Isometry3<f32>>,
     foreground_points: Vec<na30::Point3<f32>>,
     background_points: Vec<na30::Point3<f32>>,
+    background_voxels: Vec<(na30::Point3<f32>, f32)>,
     rx: flume::Receiver<GuiMsg>,
 }
 
@@ -61,6 +64,7 @@ impl Gui {
             camera,
             foreground_points: vec![],
             background_points: vec![],
+            background_voxels: vec![],
             rx,
             lidar_tf,
             ground_tf,
@@ -84,6 +88,14 @@ impl Gui {
                 na30::Point3::new(x, y, z)
             })
             .collect();
+        self.background_voxels = msg
+            .background_voxels
+            .into_iter()
+            .map(|(pt, occupancy)| {
+                let na32::coordinates::XYZ { x, y, z } = *pt;
+                (na30::Point3::new(x, y, z), occupancy)
+            })
+            .collect();
     }
 
     fn render(&self, window: &mut Window) {
@@ -105,6 +117,13 @@ impl Gui {
             filter.render_kiss3d(window);
         }
 
+        // draw background voxels, from yellow to red by occupancy
+        self.background_voxels
+            .iter()
+            .for_each(|(point, occupancy)| {
+                window.draw_point(point, &na30::Point3::new(1.0, 1.0 - occupancy, 0.0));
+            });
+
         // draw point cloud
         self.background_points.iter().for_each(|point| {
             window.draw_point(point, &na30::Point3::new(0.5, 0.5, 0.5));
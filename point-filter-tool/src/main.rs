@@ -1,14 +1,19 @@
 mod gui;
 
 use crate::gui::{Gui, GuiMsg};
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
 use clap::Parser;
 use generic_point_filter::{Config, Filter, Pt32};
 use itertools::Itertools;
 use kiss3d::{light::Light, window::Window};
 use nalgebra as na;
 use pcd_rs::{DataKind, PcdSerialize, WriterInit};
-use std::{fs, path::PathBuf};
+use range_point_filter::LabelProgram;
+use std::{
+    fs::{self, File},
+    io::{BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+};
 
 #[derive(Debug, Clone, Parser)]
 /// Point cloud filtering tool.
@@ -28,6 +33,33 @@ struct Opts {
     /// Enable graphics user interface.
     #[clap(long)]
     pub gui: bool,
+
+    /// Label configuration file. If it is provided, the region label
+    /// index of each point is written to the `label` field, or -1 if
+    /// no label matches.
+    #[clap(long)]
+    pub labels: Option<PathBuf>,
+
+    /// Background state file. If the file exists, the background
+    /// statistics are restored from it at startup. The statistics are
+    /// saved to it after all files are processed.
+    #[clap(long)]
+    pub background_state: Option<PathBuf>,
+
+    /// Write the learned background voxels to a .pcd file with an
+    /// `occupancy` field after all files are processed.
+    #[clap(long)]
+    pub export_background: Option<PathBuf>,
+
+    /// The minimum occupancy ratio of exported and displayed
+    /// background voxels. It defaults to the background threshold in
+    /// the configuration.
+    #[clap(long)]
+    pub background_threshold: Option<f64>,
+
+    /// Show the learned background voxels in the GUI.
+    #[clap(long)]
+    pub background_overlay: bool,
 }
 
 fn main() -> Result<()> {
@@ -38,6 +70,30 @@ fn main() -> Result<()> {
     };
 
     let filter = Filter::new(&config);
+    if let Some(path) = &opts.background_state {
+        if path.exists() {
+            let file = BufReader::new(File::open(path)?);
+            filter
+                .load_background_state(file)
+                .with_context(|| format!("Unable to restore {}", path.display()))?;
+        }
+    }
+
+    let background_threshold = opts.background_threshold.or_else(|| {
+        config
+            .background_filter
+            .as_ref()
+            .map(|filter| filter.config().background_threshold.raw())
+    });
+
+    let labels: Option<LabelProgram> = opts
+        .labels
+        .as_ref()
+        .map(|path| -> Result<_> {
+            let text = fs::read_to_string(path)?;
+            Ok(json5::from_str(&text)?)
+        })
+        .transpose()?;
 
     fs::create_dir_all(&opts.output_dir)?;
 
@@ -106,9 +162,18 @@ fn main() -> Result<()> {
             output_points.into_iter().map(|p| p.xyz.into()).collect();
 
         if let Some(gui_tx) = &gui_tx {
+            let background_voxels = match background_threshold {
+                Some(threshold) if opts.background_overlay => filter
+                    .export_background(threshold)?
+                    .into_iter()
+                    .map(|voxel| (na::convert(voxel.center), voxel.occupancy as f32))
+                    .collect(),
+                _ => vec![],
+            };
             let gui_msg = GuiMsg {
                 foreground_points: output_points.clone(),
                 background_points: input_points,
+                background_voxels,
             };
 
             let result = gui_tx.send(gui_msg);
@@ -119,32 +184,116 @@ fn main() -> Result<()> {
         }
 
         // Write filtered points to a .pcd file
-        #[derive(PcdSerialize)]
-        struct PcdPoint {
-            pub x: f32,
-            pub y: f32,
-            pub z: f32,
-        }
-
         let output_path = opts.output_dir.join(input_path.file_name().unwrap());
 
-        let mut writer = WriterInit {
-            width: output_points.len() as u64,
-            height: 1,
-            viewpoint: Default::default(),
-            data_kind: DataKind::Ascii,
-            schema: None,
+        match &labels {
+            Some(labels) => write_labeled_pcd(&output_path, &output_points, labels)?,
+            None => write_pcd(&output_path, &output_points)?,
         }
-        .create(output_path)?;
+    }
 
-        for point in output_points {
-            let na::coordinates::XYZ { x, y, z } = *point;
-            writer.push(&PcdPoint { x, y, z })?;
-        }
+    if let Some(path) = &opts.background_state {
+        let mut file = BufWriter::new(File::create(path)?);
+        filter.save_background_state(&mut file)?;
+        file.flush()?;
+    }
+
+    if let Some(path) = &opts.export_background {
+        let Some(threshold) = background_threshold else {
+            bail!("the background filter is not configured");
+        };
+        let voxels: Vec<(na::Point3<f32>, f32)> = filter
+            .export_background(threshold)?
+            .into_iter()
+            .map(|voxel| (na::convert(voxel.center), voxel.occupancy as f32))
+            .collect();
+        write_occupancy_pcd(path, &voxels)?;
+    }
+
+    Ok(())
+}
+
+fn write_pcd(path: &Path, points: &[na::Point3<f32>]) -> Result<()> {
+    #[derive(PcdSerialize)]
+    struct PcdPoint {
+        pub x: f32,
+        pub y: f32,
+        pub z: f32,
+    }
+
+    let mut writer = WriterInit {
+        width: points.len() as u64,
+        height: 1,
+        viewpoint: Default::default(),
+        data_kind: DataKind::Ascii,
+        schema: None,
+    }
+    .create(path)?;
+
+    for point in points {
+        let na::coordinates::XYZ { x, y, z } = **point;
+        writer.push(&PcdPoint { x, y, z })?;
+    }
+
+    writer.finish()?;
+    Ok(())
+}
+
+fn write_labeled_pcd(path: &Path, points: &[na::Point3<f32>], labels: &LabelProgram) -> Result<()> {
+    #[derive(PcdSerialize)]
+    struct PcdPoint {
+        pub x: f32,
+        pub y: f32,
+        pub z: f32,
+        pub label: i32,
+    }
+
+    let mut writer = WriterInit {
+        width: points.len() as u64,
+        height: 1,
+        viewpoint: Default::default(),
+        data_kind: DataKind::Ascii,
+        schema: None,
+    }
+    .create(path)?;
+
+    for point in points {
+        let label = match labels.classify(&na::convert(*point), None) {
+            Some(index) => index as i32,
+            None => -1,
+        };
+        let na::coordinates::XYZ { x, y, z } = **point;
+        writer.push(&PcdPoint { x, y, z, label })?;
+    }
+
+    writer.finish()?;
+    Ok(())
+}
+
+fn write_occupancy_pcd(path: &Path, voxels: &[(na::Point3<f32>, f32)]) -> Result<()> {
+    #[derive(PcdSerialize)]
+    struct PcdPoint {
+        pub x: f32,
+        pub y: f32,
+        pub z: f32,
+        pub occupancy: f32,
+    }
+
+    let mut writer = WriterInit {
+        width: voxels.len() as u64,
+        height: 1,
+        viewpoint: Default::default(),
+        data_kind: DataKind::Ascii,
+        schema: None,
+    }
+    .create(path)?;
 
-        writer.finish()?;
+    for &(point, occupancy) in voxels {
+        let na::coordinates::XYZ { x, y, z } = *point;
+        writer.push(&PcdPoint { x, y, z, occupancy })?;
     }
 
+    writer.finish()?;
     Ok(())
 }
 
@@ -0,0 +1,89 @@
+use crate::FilterProgram;
+use filter_expr::normal_form::Term;
+use nalgebra_0_32::Point3;
+
+/// An axis-aligned bounding box. The bounds can be infinite along the
+/// z axis for boxes without z limits.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min: Point3<f64>,
+    pub max: Point3<f64>,
+}
+
+impl Aabb {
+    pub fn new(min: Point3<f64>, max: Point3<f64>) -> Self {
+        Self { min, max }
+    }
+
+    /// The box containing no points.
+    pub fn empty() -> Self {
+        Self {
+            min: Point3::from([f64::INFINITY; 3]),
+            max: Point3::from([f64::NEG_INFINITY; 3]),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        (0..3).any(|i| self.min[i] > self.max[i])
+    }
+
+    pub fn contains(&self, point: &Point3<f64>) -> bool {
+        (0..3).all(|i| (self.min[i]..=self.max[i]).contains(&point[i]))
+    }
+
+    /// The smallest box containing both boxes.
+    pub fn union(&self, other: &Self) -> Self {
+        Self {
+            min: self.min.inf(&other.min),
+            max: self.max.sup(&other.max),
+        }
+    }
+
+    /// The intersection of both boxes, which can be empty.
+    pub fn intersection(&self, other: &Self) -> Self {
+        Self {
+            min: self.min.sup(&other.min),
+            max: self.max.inf(&other.max),
+        }
+    }
+}
+
+impl FilterProgram {
+    /// Computes a bounding box of the region accepted by
+    /// [`contains`](Self::contains), or `None` if the region is
+    /// unbounded.
+    ///
+    /// Item bounds are united over `+` and intersected over `*`.
+    /// Negated items and intensity items are unbounded, so they are
+    /// ignored when intersected with a box.
+    pub fn bounds(&self) -> Option<Aabb> {
+        self.bounds_impl(false)
+    }
+
+    /// Computes a bounding box of the region accepted by
+    /// [`contains_at`](Self::contains_at) at any timestamp. Items
+    /// moving along keyframes are considered unbounded.
+    pub fn bounds_over_time(&self) -> Option<Aabb> {
+        self.bounds_impl(true)
+    }
+
+    fn bounds_impl(&self, timed: bool) -> Option<Aabb> {
+        self.dnf.0.iter().try_fold(Aabb::empty(), |union, product| {
+            let bounds = product
+                .0
+                .iter()
+                .filter(|term| !term.invert)
+                .filter_map(|term| {
+                    let Term { ref ident, .. } = *term;
+                    self.items.bounds(&ident.0, timed)
+                })
+                .reduce(|lhs, rhs| lhs.intersection(&rhs))?;
+
+            if bounds.is_empty() {
+                Some(union)
+            } else {
+                Some(union.union(&bounds))
+            }
+        })
+    }
+}
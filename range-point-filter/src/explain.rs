@@ -0,0 +1,119 @@
+use crate::FilterProgram;
+use filter_expr::normal_form::Term;
+use nalgebra_0_32::Point3;
+use std::fmt::{self, Display, Formatter};
+
+/// The trace of a program evaluation on a point.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Explanation {
+    /// The program text.
+    pub program: String,
+
+    /// The truth value of every item, sorted by item names.
+    pub items: Vec<(String, bool)>,
+
+    /// The products of the program in the disjunctive normal form
+    /// and their truth values.
+    pub products: Vec<(String, bool)>,
+
+    /// The index of the first product which is true.
+    pub fired: Option<usize>,
+}
+
+impl Explanation {
+    /// Returns true if the point is accepted by the program.
+    pub fn accepted(&self) -> bool {
+        self.fired.is_some()
+    }
+}
+
+impl Display for Explanation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "program: {}", self.program)?;
+
+        writeln!(f, "items:")?;
+        for (name, yes) in &self.items {
+            writeln!(f, "  {name} = {yes}")?;
+        }
+
+        writeln!(f, "products:")?;
+        for (index, (product, yes)) in self.products.iter().enumerate() {
+            let mark = if self.fired == Some(index) {
+                " <- fired"
+            } else {
+                ""
+            };
+            writeln!(f, "  [{index}] {product} = {yes}{mark}")?;
+        }
+
+        let result = if self.accepted() {
+            "accepted"
+        } else {
+            "rejected"
+        };
+        write!(f, "result: {result}")
+    }
+}
+
+impl FilterProgram {
+    /// Explains why the point is accepted or rejected.
+    pub fn explain(&self, point: &Point3<f64>, intensity: Option<f64>) -> Explanation {
+        self.explain_impl(point, intensity, None)
+    }
+
+    /// Explains why the point is accepted or rejected at the
+    /// timestamp in seconds.
+    pub fn explain_at(
+        &self,
+        point: &Point3<f64>,
+        intensity: Option<f64>,
+        time: f64,
+    ) -> Explanation {
+        self.explain_impl(point, intensity, Some(time))
+    }
+
+    fn explain_impl(
+        &self,
+        point: &Point3<f64>,
+        intensity: Option<f64>,
+        time: Option<f64>,
+    ) -> Explanation {
+        let mut items: Vec<_> = self
+            .items
+            .names()
+            .map(|name| {
+                let yes = self.items.contains(name, point, intensity, time);
+                (name.to_string(), yes)
+            })
+            .collect();
+        items.sort_unstable();
+
+        let truth_of = |name: &str| {
+            let index = items
+                .binary_search_by(|(other, _)| other.as_str().cmp(name))
+                .unwrap();
+            items[index].1
+        };
+
+        let products: Vec<_> = self
+            .dnf
+            .0
+            .iter()
+            .map(|product| {
+                let yes = product.0.iter().all(|term| {
+                    let Term { ref ident, invert } = *term;
+                    invert ^ truth_of(&ident.0)
+                });
+                (product.to_string(), yes)
+            })
+            .collect();
+        let fired = products.iter().position(|(_, yes)| *yes);
+
+        Explanation {
+            program: self.program.to_string(),
+            items,
+            products,
+            fired,
+        }
+    }
+}
@@ -1,9 +1,9 @@
-use crate::item::Item;
-use anyhow::bail;
-use filter_expr::{
-    normal_form::{Dnf, Term},
-    parser::{Expr, ExprBinOp, Ident, Program},
+use crate::{
+    frame::FrameConfig,
+    item::{ItemConfig, ItemTable},
 };
+use anyhow::Result;
+use filter_expr::{normal_form::Dnf, parser::Program};
 use nalgebra_0_32::Point3;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -13,54 +13,34 @@ use std::collections::HashMap;
 pub struct FilterProgram {
     pub(crate) dnf: Dnf,
     pub(crate) program: Program,
-    pub(crate) items: HashMap<String, Item>,
+    pub(crate) items: ItemTable,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-struct SerializedFilterProgram {
-    program: Program,
-    items: HashMap<String, Item>,
+#[cfg_attr(feature = "with-schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "with-schemars", schemars(rename = "FilterProgram"))]
+pub(crate) struct SerializedFilterProgram {
+    pub(crate) program: Program,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub(crate) frames: HashMap<String, FrameConfig>,
+    pub(crate) items: HashMap<String, ItemConfig>,
 }
 
-impl SerializedFilterProgram {
-    fn check_program(&self, program: &Program) -> Result<(), String> {
-        self.check_expr(&program.0)
-    }
-
-    fn check_expr(&self, expr: &Expr) -> Result<(), String> {
-        match expr {
-            Expr::Ident(ident) => self.check_ident(ident),
-            Expr::UnaryOp(expr) => self.check_expr(expr),
-            Expr::BinOp(ExprBinOp { lhs, rhs, .. }) => {
-                self.check_expr(lhs)?;
-                self.check_expr(rhs)?;
-                Ok(())
-            }
-        }
-    }
-
-    fn check_ident(&self, ident: &Ident) -> Result<(), String> {
-        if self.items.contains_key(&ident.0) {
-            Ok(())
-        } else {
-            Err(ident.to_string())
-        }
-    }
-}
+#[cfg(feature = "with-schemars")]
+crate::utils::impl_json_schema_as!(FilterProgram => SerializedFilterProgram);
 
 impl TryFrom<SerializedFilterProgram> for FilterProgram {
     type Error = anyhow::Error;
 
     fn try_from(from: SerializedFilterProgram) -> Result<Self, Self::Error> {
-        if let Err(ident) = from.check_program(&from.program) {
-            bail!(r#"The item {ident}" is not defined"#);
-        }
+        let items = ItemTable::new(from.frames, from.items)?;
+        items.check_program(&from.program)?;
 
         let dnf = Dnf::from_program(from.program.clone());
 
         Ok(Self {
             dnf,
-            items: from.items,
+            items,
             program: from.program,
         })
     }
@@ -73,8 +53,10 @@ impl From<FilterProgram> for SerializedFilterProgram {
             program: cached_lang,
             ..
         } = from;
+        let (frames, items) = items.into_parts();
 
         Self {
+            frames,
             items,
             program: cached_lang,
         }
@@ -82,19 +64,25 @@ impl From<FilterProgram> for SerializedFilterProgram {
 }
 
 impl FilterProgram {
-    pub fn contains(&self, point: &Point3<f64>, intensity: Option<f64>) -> bool {
-        self.dnf.0.iter().any(|product| {
-            product.0.iter().all(|term| {
-                let Term { ref ident, invert } = *term;
-                let item = &self.items[&ident.0];
+    /// Replaces the definition of a frame and relocates the items
+    /// attached to the frame and its descendants.
+    pub fn set_frame(&mut self, name: &str, frame: FrameConfig) -> Result<()> {
+        self.items.set_frame(name, frame)
+    }
 
-                let yes = match item {
-                    Item::Box(filter) => filter.contains(point),
-                    Item::Intensity(filter) => filter.contains(intensity),
-                };
+    pub fn frames(&self) -> &HashMap<String, FrameConfig> {
+        self.items.frames()
+    }
 
-                invert ^ yes
-            })
-        })
+    /// Checks if the point is accepted. Items are placed at their
+    /// defined poses regardless of schedules and keyframes.
+    pub fn contains(&self, point: &Point3<f64>, intensity: Option<f64>) -> bool {
+        self.items.eval(&self.dnf, point, intensity, None)
+    }
+
+    /// Checks if the point is accepted at the timestamp in seconds,
+    /// taking item schedules and keyframes into account.
+    pub fn contains_at(&self, point: &Point3<f64>, intensity: Option<f64>, time: f64) -> bool {
+        self.items.eval(&self.dnf, point, intensity, Some(time))
     }
 }
@@ -0,0 +1,84 @@
+use crate::units;
+use anyhow::{bail, Result};
+use nalgebra_0_32::{Isometry3, Translation3, UnitQuaternion};
+use noisy_float::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// A named coordinate frame attached to a parent frame.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "with-schemars", derive(schemars::JsonSchema))]
+pub struct FrameConfig {
+    /// The parent frame. The frame is attached to the point frame if
+    /// it is not specified.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+
+    /// The translation relative to the parent frame.
+    #[serde(deserialize_with = "units::length")]
+    #[cfg_attr(feature = "with-schemars", schemars(with = "[units::Meters; 3]"))]
+    pub xyz: [R64; 3],
+
+    /// The roll, pitch and yaw angles relative to the parent frame.
+    #[serde(default, deserialize_with = "units::angle")]
+    #[cfg_attr(feature = "with-schemars", schemars(with = "[units::Degrees; 3]"))]
+    pub rpy_degrees: [R64; 3],
+}
+
+impl FrameConfig {
+    /// The transform from this frame to the parent frame.
+    pub fn pose(&self) -> Isometry3<f64> {
+        let [x, y, z] = self.xyz.map(|v| v.raw());
+        let [roll, pitch, yaw] = self.rpy_degrees.map(|v| v.raw().to_radians());
+
+        let trans = Translation3::new(x, y, z);
+        let rot = UnitQuaternion::from_euler_angles(roll, pitch, yaw);
+        Isometry3::from_parts(trans, rot)
+    }
+}
+
+/// Computes the transform from each frame to the point frame.
+pub(crate) fn resolve_frames(
+    frames: &HashMap<String, FrameConfig>,
+) -> Result<HashMap<String, Isometry3<f64>>> {
+    let mut poses = HashMap::with_capacity(frames.len());
+
+    for name in frames.keys() {
+        resolve_frame(frames, name, &mut poses, &mut vec![])?;
+    }
+
+    Ok(poses)
+}
+
+fn resolve_frame(
+    frames: &HashMap<String, FrameConfig>,
+    name: &str,
+    poses: &mut HashMap<String, Isometry3<f64>>,
+    visiting: &mut Vec<String>,
+) -> Result<Isometry3<f64>> {
+    if let Some(pose) = poses.get(name) {
+        return Ok(*pose);
+    }
+
+    if visiting.iter().any(|visited| visited == name) {
+        bail!(
+            r#"The frame "{name}" has a cyclic parent chain: {} -> {name}"#,
+            visiting.join(" -> ")
+        );
+    }
+
+    let Some(frame) = frames.get(name) else {
+        bail!(r#"The frame "{name}" is not defined"#);
+    };
+
+    visiting.push(name.to_string());
+    let parent_pose = match &frame.parent {
+        Some(parent) => resolve_frame(frames, parent, poses, visiting)?,
+        None => Isometry3::identity(),
+    };
+    visiting.pop();
+
+    let pose = parent_pose * frame.pose();
+    poses.insert(name.to_string(), pose);
+    Ok(pose)
+}
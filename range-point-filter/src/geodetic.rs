@@ -0,0 +1,311 @@
+use crate::{
+    item::{CorridorFilterConfig, Item, ItemConfig, PlanarBoxFilterConfig, PolygonFilterConfig},
+    units, utils,
+};
+use anyhow::{bail, ensure, Context, Result};
+use geojson::{GeoJson, Geometry, Value};
+use nalgebra_0_32::{Point2, Point3, Vector2};
+use noisy_float::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// The semi-major axis of the WGS84 ellipsoid in meters.
+const WGS84_A: f64 = 6378137.0;
+/// The flattening of the WGS84 ellipsoid.
+const WGS84_F: f64 = 1.0 / 298.257223563;
+
+/// The tolerance of the cosine between adjacent edges when
+/// recognizing rectangles.
+const RIGHT_ANGLE_TOLERANCE: f64 = 1e-3;
+
+/// The origin of the local frame on the WGS84 ellipsoid.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GeodeticOrigin {
+    pub latitude: R64,
+    pub longitude: R64,
+    #[serde(default, deserialize_with = "units::length")]
+    pub altitude: R64,
+
+    /// The compass heading of the local x axis in degrees, clockwise
+    /// from north. The local frame is east-north-up if it is 90.
+    #[serde(default = "default_heading", deserialize_with = "units::angle")]
+    pub heading_degrees: R64,
+}
+
+impl GeodeticOrigin {
+    /// Projects a geodetic position in degrees and meters to the
+    /// local frame.
+    pub fn project(&self, longitude: f64, latitude: f64, altitude: f64) -> Point3<f64> {
+        let lat0 = self.latitude.raw().to_radians();
+        let lon0 = self.longitude.raw().to_radians();
+        let origin = ecef(lat0, lon0, self.altitude.raw());
+        let point = ecef(latitude.to_radians(), longitude.to_radians(), altitude);
+        let [dx, dy, dz] = [0, 1, 2].map(|i| point[i] - origin[i]);
+
+        let (sin_lat, cos_lat) = lat0.sin_cos();
+        let (sin_lon, cos_lon) = lon0.sin_cos();
+        let east = -sin_lon * dx + cos_lon * dy;
+        let north = -sin_lat * cos_lon * dx - sin_lat * sin_lon * dy + cos_lat * dz;
+        let up = cos_lat * cos_lon * dx + cos_lat * sin_lon * dy + sin_lat * dz;
+
+        // Rotate east-north to the local x-y axes.
+        let yaw = (90.0 - self.heading_degrees.raw()).to_radians();
+        let (sin_yaw, cos_yaw) = yaw.sin_cos();
+        let x = cos_yaw * east + sin_yaw * north;
+        let y = -sin_yaw * east + cos_yaw * north;
+
+        Point3::new(x, y, up)
+    }
+
+    /// Projects a position to the local x-y plane, ignoring the
+    /// altitude.
+    fn project_2d(&self, position: &[f64]) -> Result<Point2<f64>> {
+        let [longitude, latitude, ..] = *position else {
+            bail!("a position must have at least 2 coordinates");
+        };
+        let point = self.project(longitude, latitude, self.altitude.raw());
+        Ok(Point2::new(point.x, point.y))
+    }
+}
+
+fn default_heading() -> R64 {
+    r64(90.0)
+}
+
+/// Converts geodetic coordinates in radians to the earth-centered,
+/// earth-fixed frame.
+fn ecef(latitude: f64, longitude: f64, altitude: f64) -> [f64; 3] {
+    let e2 = WGS84_F * (2.0 - WGS84_F);
+    let (sin_lat, cos_lat) = latitude.sin_cos();
+    let (sin_lon, cos_lon) = longitude.sin_cos();
+    let n = WGS84_A / (1.0 - e2 * sin_lat * sin_lat).sqrt();
+
+    [
+        (n + altitude) * cos_lat * cos_lon,
+        (n + altitude) * cos_lat * sin_lon,
+        (n * (1.0 - e2) + altitude) * sin_lat,
+    ]
+}
+
+/// The options to convert GeoJSON geometries to items.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GeoJsonOptions {
+    pub origin: GeodeticOrigin,
+
+    /// The feature property used as item names.
+    #[serde(default = "default_name_property")]
+    pub name_property: String,
+
+    /// The width of corridors converted from line strings. It is
+    /// required if the GeoJSON contains line strings.
+    #[serde(default, deserialize_with = "units::length")]
+    pub corridor_width: Option<R64>,
+
+    #[serde(default, deserialize_with = "units::length")]
+    pub z_min: Option<R64>,
+    #[serde(default, deserialize_with = "units::length")]
+    pub z_max: Option<R64>,
+}
+
+fn default_name_property() -> String {
+    "name".to_string()
+}
+
+/// Loads items from GeoJSON in WGS84 coordinates.
+///
+/// Polygons are converted to boxes if they are rectangles, or polygon
+/// items otherwise. Line strings are converted to corridors. Items
+/// are named after the feature property, and the parts of multi
+/// geometries are suffixed with their indices.
+pub fn from_geojson(text: &str, options: &GeoJsonOptions) -> Result<HashMap<String, ItemConfig>> {
+    let geojson: GeoJson = text.parse()?;
+
+    let geometries: Vec<(String, Geometry)> = match geojson {
+        GeoJson::Geometry(geometry) => vec![("geometry".to_string(), geometry)],
+        GeoJson::Feature(feature) => {
+            let name = feature_name(&feature, options, 0);
+            feature
+                .geometry
+                .into_iter()
+                .map(|g| (name.clone(), g))
+                .collect()
+        }
+        GeoJson::FeatureCollection(collection) => collection
+            .features
+            .into_iter()
+            .enumerate()
+            .filter_map(|(index, feature)| {
+                let name = feature_name(&feature, options, index);
+                Some((name, feature.geometry?))
+            })
+            .collect(),
+    };
+
+    let mut items = HashMap::new();
+
+    for (name, geometry) in geometries {
+        let converted = convert_geometry(&name, &geometry.value, options)
+            .with_context(|| format!(r#"Unable to convert the geometry of "{name}""#))?;
+
+        for (name, item) in converted {
+            ensure!(
+                !items.contains_key(&name),
+                r#"The item "{name}" is defined more than once"#
+            );
+            items.insert(name, ItemConfig::from(item));
+        }
+    }
+
+    Ok(items)
+}
+
+fn feature_name(feature: &geojson::Feature, options: &GeoJsonOptions, index: usize) -> String {
+    let name = match feature.property(&options.name_property) {
+        Some(serde_json::Value::String(name)) => name.clone(),
+        Some(serde_json::Value::Number(number)) => number.to_string(),
+        _ => format!("feature_{index}"),
+    };
+    to_identifier(&name)
+}
+
+/// Converts a name to a valid identifier in programs.
+fn to_identifier(name: &str) -> String {
+    let ident: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+
+    match ident.chars().next() {
+        Some(c) if !c.is_ascii_digit() => ident,
+        _ => format!("_{ident}"),
+    }
+}
+
+fn convert_geometry(
+    name: &str,
+    value: &Value,
+    options: &GeoJsonOptions,
+) -> Result<Vec<(String, Item)>> {
+    let indexed = |index: usize| format!("{name}_{index}");
+
+    let items = match value {
+        Value::Polygon(rings) => vec![(name.to_string(), convert_polygon(rings, options)?)],
+        Value::MultiPolygon(polygons) => polygons
+            .iter()
+            .enumerate()
+            .map(|(index, rings)| Ok((indexed(index), convert_polygon(rings, options)?)))
+            .collect::<Result<_>>()?,
+        Value::LineString(line) => vec![(name.to_string(), convert_line(line, options)?)],
+        Value::MultiLineString(lines) => lines
+            .iter()
+            .enumerate()
+            .map(|(index, line)| Ok((indexed(index), convert_line(line, options)?)))
+            .collect::<Result<_>>()?,
+        Value::GeometryCollection(geometries) => {
+            let mut items = vec![];
+            for (index, geometry) in geometries.iter().enumerate() {
+                items.extend(convert_geometry(&indexed(index), &geometry.value, options)?);
+            }
+            items
+        }
+        Value::Point(_) | Value::MultiPoint(_) => {
+            bail!("points cannot be converted to items")
+        }
+    };
+
+    Ok(items)
+}
+
+fn convert_polygon(rings: &[Vec<Vec<f64>>], options: &GeoJsonOptions) -> Result<Item> {
+    let project_ring = |ring: &Vec<Vec<f64>>| -> Result<Vec<Point2<f64>>> {
+        let mut points: Vec<_> = ring
+            .iter()
+            .map(|position| options.origin.project_2d(position))
+            .collect::<Result<_>>()?;
+
+        // GeoJSON rings repeat the first position at the end.
+        if points.len() > 1 && points.first() == points.last() {
+            points.pop();
+        }
+        Ok(points)
+    };
+
+    let Some((outer, holes)) = rings.split_first() else {
+        bail!("a polygon must have at least 1 ring");
+    };
+    let outer = project_ring(outer)?;
+    let holes: Vec<_> = holes.iter().map(project_ring).collect::<Result<_>>()?;
+
+    if holes.is_empty() {
+        if let Some(config) = to_box(&outer, options) {
+            return Ok(Item::Box(config.try_into()?));
+        }
+    }
+
+    let to_coords =
+        |ring: &[Point2<f64>]| -> Vec<_> { ring.iter().map(|p| [r64(p.x), r64(p.y)]).collect() };
+    let config = PolygonFilterConfig {
+        vertices: to_coords(&outer),
+        holes: holes.iter().map(|hole| to_coords(hole)).collect(),
+        z_min: options.z_min,
+        z_max: options.z_max,
+        z: utils::unbounded(),
+    };
+    Ok(Item::Polygon(config.try_into()?))
+}
+
+fn convert_line(line: &[Vec<f64>], options: &GeoJsonOptions) -> Result<Item> {
+    let Some(width) = options.corridor_width else {
+        bail!("the corridor width is required to convert line strings");
+    };
+
+    let path = line
+        .iter()
+        .map(|position| {
+            let p = options.origin.project_2d(position)?;
+            Ok([r64(p.x), r64(p.y)])
+        })
+        .collect::<Result<_>>()?;
+    let config = CorridorFilterConfig {
+        path,
+        width,
+        z_min: options.z_min,
+        z_max: options.z_max,
+        z: utils::unbounded(),
+    };
+    Ok(Item::Corridor(config.try_into()?))
+}
+
+/// Recognizes a rectangle and converts it to a box.
+fn to_box(ring: &[Point2<f64>], options: &GeoJsonOptions) -> Option<PlanarBoxFilterConfig> {
+    let [p0, p1, p2, p3] = *ring else {
+        return None;
+    };
+    let edges: [Vector2<f64>; 4] = [p1 - p0, p2 - p1, p3 - p2, p0 - p3];
+
+    // Zero-length edges are perpendicular to any edge, so they are
+    // ruled out first.
+    if edges.iter().any(|edge| edge.norm() <= 0.0) {
+        return None;
+    }
+    let is_rect = (0..4).all(|index| {
+        let a = &edges[index];
+        let b = &edges[(index + 1) % 4];
+        a.dot(b).abs() <= RIGHT_ANGLE_TOLERANCE * a.norm() * b.norm()
+    });
+    if !is_rect {
+        return None;
+    }
+
+    let center = Point2::from((p0.coords + p1.coords + p2.coords + p3.coords) / 4.0);
+    let azimuth = edges[0].y.atan2(edges[0].x);
+
+    Some(PlanarBoxFilterConfig {
+        xy: [r64(center.x), r64(center.y)],
+        wh: [r64(edges[0].norm()), r64(edges[1].norm())],
+        z_min: options.z_min,
+        z_max: options.z_max,
+        z: utils::unbounded(),
+        azimuth_degrees: r64(azimuth.to_degrees()),
+    })
+}
@@ -1,13 +1,176 @@
+mod corridor_filter;
 mod intensity_filter;
 mod planar_box_filter;
+mod polygon_filter;
+mod table;
 
+use crate::{
+    bounds::Aabb,
+    schedule::{Keyframe, Schedule},
+    utils::Interval,
+};
+use anyhow::{ensure, Result};
+use nalgebra_0_32::{coordinates::XYZ, Isometry2, Isometry3, Point2, Point3, Vector2, Vector3};
+use noisy_float::prelude::*;
 use serde::{Deserialize, Serialize};
+use std::ops::{Bound, Bound::*, RangeInclusive};
 
-pub use self::{intensity_filter::IntensityFilter, planar_box_filter::PlanarBoxFilter};
+pub(crate) use self::planar_box_filter::bounding_rect;
+pub(crate) use self::table::ItemTable;
+pub use self::{
+    corridor_filter::{CorridorFilter, CorridorFilterConfig},
+    intensity_filter::{IntensityFilter, IntensityFilterConfig},
+    planar_box_filter::{PlanarBoxFilter, PlanarBoxFilterConfig},
+    polygon_filter::{PolygonFilter, PolygonFilterConfig},
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "with-schemars", derive(schemars::JsonSchema))]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum Item {
     Box(PlanarBoxFilter),
+    Polygon(PolygonFilter),
+    Corridor(CorridorFilter),
     Intensity(IntensityFilter),
 }
+
+impl Item {
+    pub fn contains(&self, point: &Point3<f64>, intensity: Option<f64>) -> bool {
+        match self {
+            Item::Box(filter) => filter.contains(point),
+            Item::Polygon(filter) => filter.contains(point),
+            Item::Corridor(filter) => filter.contains(point),
+            Item::Intensity(filter) => filter.contains(intensity),
+        }
+    }
+
+    /// The bounding box of the item, or `None` if the item is not
+    /// spatially bounded.
+    pub fn bounds(&self) -> Option<Aabb> {
+        match self {
+            Item::Box(filter) => Some(filter.bounds()),
+            Item::Polygon(filter) => Some(filter.bounds()),
+            Item::Corridor(filter) => Some(filter.bounds()),
+            Item::Intensity(_) => None,
+        }
+    }
+
+    /// Relocates the item by the transform to another frame.
+    pub fn transform(&self, pose: &Isometry3<f64>) -> Result<Self> {
+        let item = match self {
+            Item::Box(filter) => Item::Box(filter.transform(pose)?),
+            Item::Polygon(filter) => Item::Polygon(filter.transform(pose)?),
+            Item::Corridor(filter) => Item::Corridor(filter.transform(pose)?),
+            Item::Intensity(filter) => Item::Intensity(filter.clone()),
+        };
+        Ok(item)
+    }
+}
+
+/// The item definition in the configuration, which may be expressed
+/// in a named frame.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "with-schemars", derive(schemars::JsonSchema))]
+pub struct ItemConfig {
+    /// The frame in which the item is defined. The item is defined in
+    /// the point frame if it is not specified.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frame: Option<String>,
+
+    /// Enables the item only within the scheduled time windows.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schedule: Option<Schedule>,
+
+    /// Moves the item along the poses interpolated from keyframes.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keyframes: Vec<Keyframe>,
+
+    #[serde(flatten)]
+    pub item: Item,
+}
+
+impl From<Item> for ItemConfig {
+    fn from(item: Item) -> Self {
+        Self {
+            frame: None,
+            schedule: None,
+            keyframes: vec![],
+            item,
+        }
+    }
+}
+
+/// The relation between a region of space and the region accepted by
+/// an item.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Overlap {
+    Inside,
+    Outside,
+    Partial,
+}
+
+/// Splits a transform into the planar transform and the height
+/// offset. The transform must not tilt the z axis.
+pub(crate) fn planar_pose(pose: &Isometry3<f64>) -> Result<(Isometry2<f64>, f64)> {
+    let up = pose.rotation * Vector3::z();
+    ensure!(
+        (up.z - 1.0).abs() <= 1e-9,
+        "planar items can only be placed in frames rotated around the z axis"
+    );
+
+    let XYZ { x, y, z } = *pose.translation.vector;
+    let (_, _, yaw) = pose.rotation.euler_angles();
+    Ok((Isometry2::new(Vector2::new(x, y), yaw), z))
+}
+
+/// Converts the z bound and the legacy inclusive z limits to an
+/// interval, which is unbounded on the missing sides.
+pub(crate) fn z_range(
+    z: &(Bound<R64>, Bound<R64>),
+    z_min: Option<R64>,
+    z_max: Option<R64>,
+) -> Result<Interval> {
+    let interval = Interval::from_config(z, z_min.map(Included), z_max.map(Included))?;
+    ensure!(!interval.is_empty(), "{interval} is not a valid bound");
+    Ok(interval)
+}
+
+/// The legacy z limits and the z bound in configs.
+pub(crate) type ZLimits = (Option<R64>, Option<R64>, (Bound<R64>, Bound<R64>));
+
+/// Converts a z interval back to the config. Inclusive limits are
+/// written in the legacy `z_min` and `z_max` fields, and exclusive
+/// ones in the bound syntax.
+pub(crate) fn z_limits(z_range: &Interval) -> ZLimits {
+    let (lower, upper) = z_range.to_config();
+    let split = |bound: Bound<R64>| match bound {
+        Included(value) => (Some(value), Unbounded),
+        bound => (None, bound),
+    };
+    let (z_min, lower) = split(lower);
+    let (z_max, upper) = split(upper);
+    (z_min, z_max, (lower, upper))
+}
+
+/// Checks if the axis-aligned rectangles overlap.
+pub(crate) fn rects_overlap(
+    lhs: &[RangeInclusive<f64>; 2],
+    rhs: &[RangeInclusive<f64>; 2],
+) -> bool {
+    lhs.iter()
+        .zip(rhs)
+        .all(|(l, r)| l.start() <= r.end() && r.start() <= l.end())
+}
+
+/// Converts coordinate pairs to points.
+pub(crate) fn to_points(coords: &[[R64; 2]]) -> Vec<Point2<f64>> {
+    coords
+        .iter()
+        .map(|[x, y]| Point2::new(x.raw(), y.raw()))
+        .collect()
+}
+
+/// Converts points to coordinate pairs.
+pub(crate) fn from_points(points: &[Point2<f64>]) -> Vec<[R64; 2]> {
+    points.iter().map(|p| [r64(p.x), r64(p.y)]).collect()
+}
@@ -0,0 +1,180 @@
+use super::{
+    bounding_rect, from_points, planar_pose, rects_overlap, to_points, z_limits, z_range, Overlap,
+};
+use crate::{
+    bounds::Aabb,
+    units,
+    utils::{self, Interval},
+};
+use anyhow::ensure;
+use nalgebra_0_32::{Isometry3, Point2, Point3};
+use noisy_float::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::ops::{Bound, RangeInclusive};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "with-schemars", derive(schemars::JsonSchema))]
+pub struct CorridorFilterConfig {
+    /// The vertices of the center line.
+    #[serde(deserialize_with = "units::length")]
+    #[cfg_attr(feature = "with-schemars", schemars(with = "Vec<[units::Meters; 2]>"))]
+    pub path: Vec<[R64; 2]>,
+
+    /// The full width of the corridor.
+    #[serde(deserialize_with = "units::length")]
+    #[cfg_attr(feature = "with-schemars", schemars(with = "units::Meters"))]
+    pub width: R64,
+
+    /// The inclusive lower z limit.
+    #[serde(
+        default,
+        deserialize_with = "units::length",
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[cfg_attr(feature = "with-schemars", schemars(with = "Option<units::Meters>"))]
+    pub z_min: Option<R64>,
+
+    /// The inclusive upper z limit.
+    #[serde(
+        default,
+        deserialize_with = "units::length",
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[cfg_attr(feature = "with-schemars", schemars(with = "Option<units::Meters>"))]
+    pub z_max: Option<R64>,
+
+    /// The z limits in the bound syntax, such as `{">": 0, "<=": 2}`.
+    #[serde(
+        serialize_with = "utils::serde_bound::serialize",
+        deserialize_with = "units::length",
+        default = "utils::unbounded",
+        skip_serializing_if = "utils::is_unbounded"
+    )]
+    #[cfg_attr(
+        feature = "with-schemars",
+        schemars(with = "utils::SerializedBound<units::Meters>")
+    )]
+    pub z: (Bound<R64>, Bound<R64>),
+}
+
+/// The region within a distance to a polyline, such as a lane along
+/// its center line.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "CorridorFilterConfig", into = "CorridorFilterConfig")]
+pub struct CorridorFilter {
+    pub(crate) path: Vec<Point2<f64>>,
+    pub(crate) width: f64,
+    pub(crate) z_range: Interval,
+}
+
+impl CorridorFilter {
+    pub fn contains(&self, point: &Point3<f64>) -> bool {
+        if !self.z_range.contains(point.z) {
+            return false;
+        }
+
+        let point = Point2::new(point.x, point.y);
+        let radius = self.width / 2.0;
+
+        if let [only] = self.path.as_slice() {
+            return (point - only).norm() <= radius;
+        }
+
+        self.path.windows(2).any(|segment| {
+            let [a, b] = [segment[0], segment[1]];
+            let ab = b - a;
+            let ratio = ((point - a).dot(&ab) / ab.norm_squared()).clamp(0.0, 1.0);
+            let ratio = if ratio.is_finite() { ratio } else { 0.0 };
+            (point - (a + ab * ratio)).norm() <= radius
+        })
+    }
+
+    pub fn path(&self) -> &[Point2<f64>] {
+        &self.path
+    }
+
+    pub fn width(&self) -> f64 {
+        self.width
+    }
+
+    pub fn bounds(&self) -> Aabb {
+        let [rx, ry] = self.bounding_rect();
+        Aabb::new(
+            Point3::new(*rx.start(), *ry.start(), self.z_range.start()),
+            Point3::new(*rx.end(), *ry.end(), self.z_range.end()),
+        )
+    }
+
+    /// Checks how the axis-aligned rectangle overlaps with the
+    /// corridor. Only disjoint bounding rectangles are reported as
+    /// outside.
+    pub(crate) fn overlap_2d(
+        &self,
+        x_range: &RangeInclusive<f64>,
+        y_range: &RangeInclusive<f64>,
+    ) -> Overlap {
+        let rect = [x_range.clone(), y_range.clone()];
+        if rects_overlap(&self.bounding_rect(), &rect) {
+            Overlap::Partial
+        } else {
+            Overlap::Outside
+        }
+    }
+
+    /// Relocates the corridor by the transform to another frame. The
+    /// transform must not tilt the z axis.
+    pub fn transform(&self, pose: &Isometry3<f64>) -> anyhow::Result<Self> {
+        let (tf, z) = planar_pose(pose)?;
+
+        Ok(Self {
+            path: self.path.iter().map(|p| tf * p).collect(),
+            width: self.width,
+            z_range: self.z_range.shift(z),
+        })
+    }
+
+    fn bounding_rect(&self) -> [RangeInclusive<f64>; 2] {
+        let radius = self.width / 2.0;
+        bounding_rect(&self.path).map(|r| (r.start() - radius)..=(r.end() + radius))
+    }
+}
+
+#[cfg(feature = "with-schemars")]
+crate::utils::impl_json_schema_as!(CorridorFilter => CorridorFilterConfig);
+
+impl TryFrom<CorridorFilterConfig> for CorridorFilter {
+    type Error = anyhow::Error;
+
+    fn try_from(config: CorridorFilterConfig) -> Result<Self, Self::Error> {
+        let CorridorFilterConfig {
+            path,
+            width,
+            z_min,
+            z_max,
+            z,
+        } = config;
+
+        ensure!(!path.is_empty(), "a corridor needs at least 1 vertex");
+        ensure!(width >= 0.0, "the corridor width must be non-negative");
+
+        Ok(Self {
+            path: to_points(&path),
+            width: width.raw(),
+            z_range: z_range(&z, z_min, z_max)?,
+        })
+    }
+}
+
+impl From<CorridorFilter> for CorridorFilterConfig {
+    fn from(from: CorridorFilter) -> Self {
+        let (z_min, z_max, z) = z_limits(&from.z_range);
+
+        Self {
+            path: from_points(&from.path),
+            width: r64(from.width),
+            z_min,
+            z_max,
+            z,
+        }
+    }
+}
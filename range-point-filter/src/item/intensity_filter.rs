@@ -1,17 +1,38 @@
+use crate::utils::{self, Interval};
 use noisy_float::prelude::*;
 use serde::{Deserialize, Serialize};
-use std::ops::Range;
+use std::ops::{Bound, Bound::*};
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "with-schemars", derive(schemars::JsonSchema))]
 pub struct IntensityFilterConfig {
-    pub min: R64,
+    /// The legacy inclusive lower limit.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "with-schemars", schemars(with = "Option<f64>"))]
+    pub min: Option<R64>,
+
+    /// The legacy exclusive upper limit.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "with-schemars", schemars(with = "Option<f64>"))]
     pub max: Option<R64>,
+
+    /// The limits in the bound syntax, such as `{">=": 10, "<=": 50}`.
+    #[serde(
+        with = "utils::serde_bound",
+        default = "utils::unbounded",
+        skip_serializing_if = "utils::is_unbounded"
+    )]
+    #[cfg_attr(
+        feature = "with-schemars",
+        schemars(with = "utils::SerializedBound<f64>")
+    )]
+    pub range: (Bound<R64>, Bound<R64>),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(from = "IntensityFilterConfig", into = "IntensityFilterConfig")]
+#[serde(try_from = "IntensityFilterConfig", into = "IntensityFilterConfig")]
 pub struct IntensityFilter {
-    range: Range<N64>,
+    range: Interval,
 }
 
 impl IntensityFilter {
@@ -24,37 +45,35 @@ impl IntensityFilter {
             return true;
         };
 
-        // reject NaN
-        let intensity = if let Some(intensity) = N64::try_new(intensity) {
-            intensity
-        } else {
-            return false;
-        };
+        // NaN is rejected by the interval.
+        self.range.contains(intensity)
+    }
 
-        self.range.contains(&intensity)
+    /// Returns true if no intensity is accepted.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.range.is_empty()
     }
 }
 
-impl From<IntensityFilterConfig> for IntensityFilter {
-    fn from(config: IntensityFilterConfig) -> Self {
-        let min = config.min.raw();
-        let max = config.max.map(|max| max.raw()).unwrap_or(f64::INFINITY);
+#[cfg(feature = "with-schemars")]
+crate::utils::impl_json_schema_as!(IntensityFilter => IntensityFilterConfig);
 
-        let min = n64(min);
-        let max = n64(max);
+impl TryFrom<IntensityFilterConfig> for IntensityFilter {
+    type Error = anyhow::Error;
 
-        let range = min..max;
-        Self { range }
+    fn try_from(config: IntensityFilterConfig) -> Result<Self, Self::Error> {
+        let IntensityFilterConfig { min, max, range } = config;
+        let range = Interval::from_config(&range, min.map(Included), max.map(Excluded))?;
+        Ok(Self { range })
     }
 }
 
 impl From<IntensityFilter> for IntensityFilterConfig {
-    fn from(config: IntensityFilter) -> Self {
-        let min = r64(config.range.start.raw());
-        let max = {
-            let max = config.range.end;
-            max.is_finite().then(|| r64(max.raw()))
-        };
-        Self { min, max }
+    fn from(from: IntensityFilter) -> Self {
+        Self {
+            min: None,
+            max: None,
+            range: from.range.to_config(),
+        }
     }
 }
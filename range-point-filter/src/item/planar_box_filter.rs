@@ -1,4 +1,9 @@
-use anyhow::ensure;
+use super::{planar_pose, z_limits, z_range, Overlap};
+use crate::{
+    bounds::Aabb,
+    units,
+    utils::{self, Interval},
+};
 use itertools::izip;
 use nalgebra_0_32::{
     coordinates::{XY, XYZ},
@@ -6,14 +11,52 @@ use nalgebra_0_32::{
 };
 use noisy_float::prelude::*;
 use serde::{Deserialize, Serialize};
-use std::ops::RangeInclusive;
+use std::ops::{Bound, RangeInclusive};
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "with-schemars", derive(schemars::JsonSchema))]
 pub struct PlanarBoxFilterConfig {
+    #[serde(deserialize_with = "units::length")]
+    #[cfg_attr(feature = "with-schemars", schemars(with = "[units::Meters; 2]"))]
     pub xy: [R64; 2],
+
+    #[serde(deserialize_with = "units::length")]
+    #[cfg_attr(feature = "with-schemars", schemars(with = "[units::Meters; 2]"))]
     pub wh: [R64; 2],
+
+    /// The inclusive lower z limit.
+    #[serde(
+        default,
+        deserialize_with = "units::length",
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[cfg_attr(feature = "with-schemars", schemars(with = "Option<units::Meters>"))]
     pub z_min: Option<R64>,
+
+    /// The inclusive upper z limit.
+    #[serde(
+        default,
+        deserialize_with = "units::length",
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[cfg_attr(feature = "with-schemars", schemars(with = "Option<units::Meters>"))]
     pub z_max: Option<R64>,
+
+    /// The z limits in the bound syntax, such as `{">": 0, "<=": 2}`.
+    #[serde(
+        serialize_with = "utils::serde_bound::serialize",
+        deserialize_with = "units::length",
+        default = "utils::unbounded",
+        skip_serializing_if = "utils::is_unbounded"
+    )]
+    #[cfg_attr(
+        feature = "with-schemars",
+        schemars(with = "utils::SerializedBound<units::Meters>")
+    )]
+    pub z: (Bound<R64>, Bound<R64>),
+
+    #[serde(deserialize_with = "units::angle")]
+    #[cfg_attr(feature = "with-schemars", schemars(with = "units::Degrees"))]
     pub azimuth_degrees: R64,
 }
 
@@ -22,7 +65,8 @@ pub struct PlanarBoxFilterConfig {
 pub struct PlanarBoxFilter {
     pub(crate) tf_2d: Isometry2<f64>,
     pub(crate) tf_2d_inv: Isometry2<f64>,
-    pub(crate) ranges: [RangeInclusive<f64>; 3],
+    pub(crate) ranges: [RangeInclusive<f64>; 2],
+    pub(crate) z_range: Interval,
 }
 
 impl PlanarBoxFilter {
@@ -30,7 +74,7 @@ impl PlanarBoxFilter {
         let XYZ { x, y, z } = **point;
         let point_2d = self.tf_2d_inv * Point2::new(x, y);
         let XY { x, y } = *point_2d;
-        izip!([x, y, z], &self.ranges).all(|(v, range)| range.contains(&v))
+        izip!([x, y], &self.ranges).all(|(v, range)| range.contains(&v)) && self.z_range.contains(z)
     }
 
     pub fn pose_3d(&self) -> Option<Isometry3<f64>> {
@@ -60,26 +104,129 @@ impl PlanarBoxFilter {
     pub fn extends_3d(&self) -> Option<[f64; 3]> {
         let len = |r: &RangeInclusive<f64>| (r.end() - r.start());
 
-        let [rx, ry, rz] = &self.ranges;
-        let ok = rz.start().is_finite() && rz.end().is_finite();
-        ok.then(|| [len(rx), len(ry), len(rz)])
+        let [rx, ry] = &self.ranges;
+        let rz = &self.z_range;
+        rz.is_bounded()
+            .then(|| [len(rx), len(ry), rz.end() - rz.start()])
     }
 
     pub fn extends_2d(&self) -> [f64; 2] {
         let len = |r: &RangeInclusive<f64>| (r.end() - r.start());
-        let [rx, ry, _] = &self.ranges;
+        let [rx, ry] = &self.ranges;
         [len(rx), len(ry)]
     }
 
+    /// The corners of the box footprint in counter-clockwise order.
+    pub fn corners_2d(&self) -> [Point2<f64>; 4] {
+        let [rx, ry] = &self.ranges;
+        [
+            Point2::new(*rx.start(), *ry.start()),
+            Point2::new(*rx.end(), *ry.start()),
+            Point2::new(*rx.end(), *ry.end()),
+            Point2::new(*rx.start(), *ry.end()),
+        ]
+        .map(|corner| self.tf_2d * corner)
+    }
+
+    /// The axis-aligned bounding box of the box.
+    pub fn bounds(&self) -> Aabb {
+        let [rx, ry] = bounding_rect(&self.corners_2d());
+        let rz = &self.z_range;
+        Aabb::new(
+            Point3::new(*rx.start(), *ry.start(), rz.start()),
+            Point3::new(*rx.end(), *ry.end(), rz.end()),
+        )
+    }
+
+    /// Checks how the axis-aligned rectangle `[x_range] x [y_range]`
+    /// overlaps with the box footprint.
+    pub(crate) fn overlap_2d(
+        &self,
+        x_range: &RangeInclusive<f64>,
+        y_range: &RangeInclusive<f64>,
+    ) -> Overlap {
+        let [rx, ry] = &self.ranges;
+
+        // Transform the rectangle corners into the box frame.
+        let corners = [
+            Point2::new(*x_range.start(), *y_range.start()),
+            Point2::new(*x_range.end(), *y_range.start()),
+            Point2::new(*x_range.end(), *y_range.end()),
+            Point2::new(*x_range.start(), *y_range.end()),
+        ]
+        .map(|corner| self.tf_2d_inv * corner);
+
+        let inside = corners
+            .iter()
+            .all(|corner| rx.contains(&corner.x) && ry.contains(&corner.y));
+        if inside {
+            return Overlap::Inside;
+        }
+
+        // Separating axis test on the axes of both rectangles.
+        let disjoint =
+            |points: &[Point2<f64>], x: &RangeInclusive<f64>, y: &RangeInclusive<f64>| {
+                let [px, py] = bounding_rect(points);
+                px.end() < x.start()
+                    || px.start() > x.end()
+                    || py.end() < y.start()
+                    || py.start() > y.end()
+            };
+
+        if disjoint(&corners, rx, ry) || disjoint(&self.corners_2d(), x_range, y_range) {
+            Overlap::Outside
+        } else {
+            Overlap::Partial
+        }
+    }
+
+    /// Relocates the box by the transform to another frame. The
+    /// transform must not tilt the z axis.
+    pub fn transform(&self, pose: &Isometry3<f64>) -> anyhow::Result<Self> {
+        let (tf, z) = planar_pose(pose)?;
+
+        let tf_2d = tf * self.tf_2d;
+        let tf_2d_inv = tf_2d.inverse();
+
+        Ok(Self {
+            tf_2d,
+            tf_2d_inv,
+            ranges: self.ranges.clone(),
+            z_range: self.z_range.shift(z),
+        })
+    }
+
+    /// Returns true if the box has no limit on the z axis.
+    pub fn is_planar(&self) -> bool {
+        self.z_range.is_unbounded()
+    }
+
     fn z_center(&self) -> Option<f64> {
-        let [_, _, z_range] = &self.ranges;
-        let start = z_range.start();
-        let end = z_range.end();
-        let ok = start.is_finite() && end.is_finite();
-        ok.then(|| start + (end - start) / 2.0)
+        let start = self.z_range.start();
+        let end = self.z_range.end();
+        self.z_range
+            .is_bounded()
+            .then(|| start + (end - start) / 2.0)
     }
 }
 
+/// Computes the axis-aligned bounding rectangle of a set of points.
+pub(crate) fn bounding_rect(points: &[Point2<f64>]) -> [RangeInclusive<f64>; 2] {
+    let (min, max) = points.iter().fold(
+        ([f64::INFINITY; 2], [f64::NEG_INFINITY; 2]),
+        |([min_x, min_y], [max_x, max_y]), p| {
+            (
+                [min_x.min(p.x), min_y.min(p.y)],
+                [max_x.max(p.x), max_y.max(p.y)],
+            )
+        },
+    );
+    [min[0]..=max[0], min[1]..=max[1]]
+}
+
+#[cfg(feature = "with-schemars")]
+crate::utils::impl_json_schema_as!(PlanarBoxFilter => PlanarBoxFilterConfig);
+
 impl TryFrom<PlanarBoxFilterConfig> for PlanarBoxFilter {
     type Error = anyhow::Error;
 
@@ -90,28 +237,18 @@ impl TryFrom<PlanarBoxFilterConfig> for PlanarBoxFilter {
             wh,
             z_min,
             z_max,
+            z,
         } = config;
         let yaw = azimuth_degrees.raw().to_radians();
-
-        let z_min = match z_min {
-            Some(val) => val.raw(),
-            None => f64::neg_infinity(),
-        };
-        let z_max = match z_max {
-            Some(val) => val.raw(),
-            None => f64::infinity(),
-        };
+        let z_range = z_range(&z, z_min, z_max)?;
 
         let ranges = {
-            ensure!(z_min <= z_max, "[{z_min}, {z_max}] is not a valid bound");
-
             let [ex, ey] = wh;
             let hx = ex.raw() / 2.0;
             let hy = ey.raw() / 2.0;
             let x_range = (-hx)..=hx;
             let y_range = (-hy)..=hy;
-            let z_range = z_min..=z_max;
-            [x_range, y_range, z_range]
+            [x_range, y_range]
         };
 
         let tf_2d = Isometry2 {
@@ -124,6 +261,7 @@ impl TryFrom<PlanarBoxFilterConfig> for PlanarBoxFilter {
             tf_2d,
             tf_2d_inv,
             ranges,
+            z_range,
         })
     }
 }
@@ -137,11 +275,10 @@ impl From<PlanarBoxFilter> for PlanarBoxFilterConfig {
             [r64(x), r64(y)]
         };
         let azimuth_degrees = r64(from.tf_2d.rotation.angle().to_degrees());
-        let [rx, ry, rz] = from.ranges;
+        let [rx, ry] = from.ranges;
 
         let wh = [len(rx), len(ry)];
-        let z_min = R64::try_new(*rz.start());
-        let z_max = R64::try_new(*rz.end());
+        let (z_min, z_max, z) = z_limits(&from.z_range);
 
         Self {
             azimuth_degrees,
@@ -149,6 +286,7 @@ impl From<PlanarBoxFilter> for PlanarBoxFilterConfig {
             wh,
             z_min,
             z_max,
+            z,
         }
     }
 }
@@ -0,0 +1,195 @@
+use super::{
+    bounding_rect, from_points, planar_pose, rects_overlap, to_points, z_limits, z_range, Overlap,
+};
+use crate::{
+    bounds::Aabb,
+    units,
+    utils::{self, Interval},
+};
+use anyhow::ensure;
+use nalgebra_0_32::{Isometry3, Point2, Point3};
+use noisy_float::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::ops::{Bound, RangeInclusive};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "with-schemars", derive(schemars::JsonSchema))]
+pub struct PolygonFilterConfig {
+    /// The vertices of the outer boundary.
+    #[serde(deserialize_with = "units::length")]
+    #[cfg_attr(feature = "with-schemars", schemars(with = "Vec<[units::Meters; 2]>"))]
+    pub vertices: Vec<[R64; 2]>,
+
+    /// The vertices of holes inside the outer boundary.
+    #[serde(
+        default,
+        deserialize_with = "units::length",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    #[cfg_attr(
+        feature = "with-schemars",
+        schemars(with = "Vec<Vec<[units::Meters; 2]>>")
+    )]
+    pub holes: Vec<Vec<[R64; 2]>>,
+
+    /// The inclusive lower z limit.
+    #[serde(
+        default,
+        deserialize_with = "units::length",
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[cfg_attr(feature = "with-schemars", schemars(with = "Option<units::Meters>"))]
+    pub z_min: Option<R64>,
+
+    /// The inclusive upper z limit.
+    #[serde(
+        default,
+        deserialize_with = "units::length",
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[cfg_attr(feature = "with-schemars", schemars(with = "Option<units::Meters>"))]
+    pub z_max: Option<R64>,
+
+    /// The z limits in the bound syntax, such as `{">": 0, "<=": 2}`.
+    #[serde(
+        serialize_with = "utils::serde_bound::serialize",
+        deserialize_with = "units::length",
+        default = "utils::unbounded",
+        skip_serializing_if = "utils::is_unbounded"
+    )]
+    #[cfg_attr(
+        feature = "with-schemars",
+        schemars(with = "utils::SerializedBound<units::Meters>")
+    )]
+    pub z: (Bound<R64>, Bound<R64>),
+}
+
+/// A vertical prism over a simple polygon, which may have holes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "PolygonFilterConfig", into = "PolygonFilterConfig")]
+pub struct PolygonFilter {
+    pub(crate) vertices: Vec<Point2<f64>>,
+    pub(crate) holes: Vec<Vec<Point2<f64>>>,
+    pub(crate) z_range: Interval,
+}
+
+impl PolygonFilter {
+    pub fn contains(&self, point: &Point3<f64>) -> bool {
+        if !self.z_range.contains(point.z) {
+            return false;
+        }
+
+        // Even-odd rule over all rings.
+        let point = Point2::new(point.x, point.y);
+        let crossings = std::iter::once(&self.vertices)
+            .chain(&self.holes)
+            .filter(|ring| crosses(ring, &point))
+            .count();
+        crossings % 2 == 1
+    }
+
+    pub fn vertices(&self) -> &[Point2<f64>] {
+        &self.vertices
+    }
+
+    pub fn holes(&self) -> &[Vec<Point2<f64>>] {
+        &self.holes
+    }
+
+    pub fn bounds(&self) -> Aabb {
+        let [rx, ry] = bounding_rect(&self.vertices);
+        Aabb::new(
+            Point3::new(*rx.start(), *ry.start(), self.z_range.start()),
+            Point3::new(*rx.end(), *ry.end(), self.z_range.end()),
+        )
+    }
+
+    /// Checks how the axis-aligned rectangle overlaps with the
+    /// polygon. Only disjoint bounding rectangles are reported as
+    /// outside.
+    pub(crate) fn overlap_2d(
+        &self,
+        x_range: &RangeInclusive<f64>,
+        y_range: &RangeInclusive<f64>,
+    ) -> Overlap {
+        let rect = [x_range.clone(), y_range.clone()];
+        if rects_overlap(&bounding_rect(&self.vertices), &rect) {
+            Overlap::Partial
+        } else {
+            Overlap::Outside
+        }
+    }
+
+    /// Relocates the polygon by the transform to another frame. The
+    /// transform must not tilt the z axis.
+    pub fn transform(&self, pose: &Isometry3<f64>) -> anyhow::Result<Self> {
+        let (tf, z) = planar_pose(pose)?;
+        let transform = |ring: &[Point2<f64>]| -> Vec<_> { ring.iter().map(|p| tf * p).collect() };
+
+        Ok(Self {
+            vertices: transform(&self.vertices),
+            holes: self.holes.iter().map(|hole| transform(hole)).collect(),
+            z_range: self.z_range.shift(z),
+        })
+    }
+}
+
+/// Checks if a ray from the point towards +x crosses the ring an odd
+/// number of times.
+fn crosses(ring: &[Point2<f64>], point: &Point2<f64>) -> bool {
+    let mut inside = false;
+
+    for (index, a) in ring.iter().enumerate() {
+        let b = &ring[(index + 1) % ring.len()];
+        if (a.y > point.y) != (b.y > point.y) {
+            let x = a.x + (point.y - a.y) / (b.y - a.y) * (b.x - a.x);
+            if point.x < x {
+                inside = !inside;
+            }
+        }
+    }
+
+    inside
+}
+
+#[cfg(feature = "with-schemars")]
+crate::utils::impl_json_schema_as!(PolygonFilter => PolygonFilterConfig);
+
+impl TryFrom<PolygonFilterConfig> for PolygonFilter {
+    type Error = anyhow::Error;
+
+    fn try_from(config: PolygonFilterConfig) -> Result<Self, Self::Error> {
+        let PolygonFilterConfig {
+            vertices,
+            holes,
+            z_min,
+            z_max,
+            z,
+        } = config;
+
+        ensure!(vertices.len() >= 3, "a polygon needs at least 3 vertices");
+        for hole in &holes {
+            ensure!(hole.len() >= 3, "a polygon hole needs at least 3 vertices");
+        }
+
+        Ok(Self {
+            vertices: to_points(&vertices),
+            holes: holes.iter().map(|hole| to_points(hole)).collect(),
+            z_range: z_range(&z, z_min, z_max)?,
+        })
+    }
+}
+
+impl From<PolygonFilter> for PolygonFilterConfig {
+    fn from(from: PolygonFilter) -> Self {
+        let (z_min, z_max, z) = z_limits(&from.z_range);
+
+        Self {
+            vertices: from_points(&from.vertices),
+            holes: from.holes.iter().map(|hole| from_points(hole)).collect(),
+            z_min,
+            z_max,
+            z,
+        }
+    }
+}
@@ -0,0 +1,188 @@
+use super::{Item, ItemConfig};
+use crate::{
+    bounds::Aabb,
+    frame::{resolve_frames, FrameConfig},
+    schedule::{check_keyframes, interpolate},
+};
+use anyhow::{bail, Context, Result};
+use filter_expr::{
+    normal_form::{Dnf, Term},
+    parser::{Expr, ExprBinOp, Program},
+};
+use nalgebra_0_32::{Isometry3, Point3};
+use std::collections::HashMap;
+
+/// The named items shared by programs, along with the frames in
+/// which the items are defined.
+#[derive(Debug, Clone)]
+pub(crate) struct ItemTable {
+    frames: HashMap<String, FrameConfig>,
+    configs: HashMap<String, ItemConfig>,
+    /// The transforms from frames to the point frame.
+    poses: HashMap<String, Isometry3<f64>>,
+    /// The items resolved to the point frame.
+    items: HashMap<String, Item>,
+}
+
+impl ItemTable {
+    pub fn new(
+        frames: HashMap<String, FrameConfig>,
+        configs: HashMap<String, ItemConfig>,
+    ) -> Result<Self> {
+        let poses = resolve_frames(&frames)?;
+        let items = resolve_items(&poses, &configs)?;
+
+        Ok(Self {
+            frames,
+            configs,
+            poses,
+            items,
+        })
+    }
+
+    pub fn into_parts(self) -> (HashMap<String, FrameConfig>, HashMap<String, ItemConfig>) {
+        (self.frames, self.configs)
+    }
+
+    pub fn frames(&self) -> &HashMap<String, FrameConfig> {
+        &self.frames
+    }
+
+    /// Gets the item resolved to the point frame.
+    pub fn get(&self, name: &str) -> &Item {
+        &self.items[name]
+    }
+
+    /// The bounding box of the item. Items moving along keyframes
+    /// are unbounded if `timed` is true.
+    pub fn bounds(&self, name: &str, timed: bool) -> Option<Aabb> {
+        if timed && !self.configs[name].keyframes.is_empty() {
+            return None;
+        }
+        self.items[name].bounds()
+    }
+
+    /// Iterates over the item names.
+    pub fn names(&self) -> impl Iterator<Item = &str> + '_ {
+        self.items.keys().map(|name| name.as_str())
+    }
+
+    /// Replaces the definition of a frame and relocates the items
+    /// attached to the frame and its descendants.
+    pub fn set_frame(&mut self, name: &str, frame: FrameConfig) -> Result<()> {
+        let mut frames = self.frames.clone();
+        frames.insert(name.to_string(), frame);
+        *self = Self::new(frames, self.configs.clone())?;
+        Ok(())
+    }
+
+    /// Checks that every item referred by the program is defined.
+    pub fn check_program(&self, program: &Program) -> Result<()> {
+        self.check_expr(&program.0)
+    }
+
+    fn check_expr(&self, expr: &Expr) -> Result<()> {
+        match expr {
+            Expr::Ident(ident) => {
+                if !self.items.contains_key(&ident.0) {
+                    bail!(r#"The item "{ident}" is not defined"#);
+                }
+                Ok(())
+            }
+            Expr::UnaryOp(expr) => self.check_expr(expr),
+            Expr::BinOp(ExprBinOp { lhs, rhs, .. }) => {
+                self.check_expr(lhs)?;
+                self.check_expr(rhs)?;
+                Ok(())
+            }
+        }
+    }
+
+    /// Evaluates a program in the disjunctive normal form. Items are
+    /// placed at their defined poses if the timestamp is not given.
+    pub fn eval(
+        &self,
+        dnf: &Dnf,
+        point: &Point3<f64>,
+        intensity: Option<f64>,
+        time: Option<f64>,
+    ) -> bool {
+        dnf.0.iter().any(|product| {
+            product.0.iter().all(|term| {
+                let Term { ref ident, invert } = *term;
+                invert ^ self.contains(&ident.0, point, intensity, time)
+            })
+        })
+    }
+
+    pub fn contains(
+        &self,
+        name: &str,
+        point: &Point3<f64>,
+        intensity: Option<f64>,
+        time: Option<f64>,
+    ) -> bool {
+        let Some(time) = time else {
+            return self.items[name].contains(point, intensity);
+        };
+        let config = &self.configs[name];
+
+        if let Some(schedule) = &config.schedule {
+            if !schedule.is_enabled(time) {
+                return false;
+            }
+        }
+
+        let Some(motion) = interpolate(&config.keyframes, time) else {
+            return self.items[name].contains(point, intensity);
+        };
+
+        // Move the point into the item definition instead of moving
+        // the item.
+        let pose = match &config.frame {
+            Some(frame) => self.poses[frame] * motion,
+            None => motion,
+        };
+        let point = pose.inverse_transform_point(point);
+        config.item.contains(&point, intensity)
+    }
+}
+
+/// Transforms the items to the point frame.
+fn resolve_items(
+    poses: &HashMap<String, Isometry3<f64>>,
+    configs: &HashMap<String, ItemConfig>,
+) -> Result<HashMap<String, Item>> {
+    configs
+        .iter()
+        .map(|(name, config)| {
+            let ItemConfig {
+                frame,
+                schedule,
+                keyframes,
+                item,
+            } = config;
+
+            if let Some(schedule) = schedule {
+                schedule
+                    .check()
+                    .with_context(|| format!(r#"Invalid schedule of item "{name}""#))?;
+            }
+            check_keyframes(keyframes)
+                .with_context(|| format!(r#"Invalid keyframes of item "{name}""#))?;
+
+            let item = match frame {
+                Some(frame) => {
+                    let Some(pose) = poses.get(frame) else {
+                        bail!(r#"The frame "{frame}" of item "{name}" is not defined"#);
+                    };
+                    item.transform(pose)
+                        .with_context(|| format!(r#"Unable to place item "{name}""#))?
+                }
+                None => item.clone(),
+            };
+
+            Ok((name.clone(), item))
+        })
+        .collect()
+}
@@ -0,0 +1,188 @@
+use crate::{
+    frame::FrameConfig,
+    item::{ItemConfig, ItemTable},
+};
+use anyhow::{ensure, Result};
+use filter_expr::{normal_form::Dnf, parser::Program};
+use nalgebra_0_32::Point3;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Assigns region labels to points using an ordered list of programs
+/// sharing the same items.
+///
+/// The labels are ordered by priority. Where regions overlap, the
+/// point is given the first matching label.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "SerializedLabelProgram", into = "SerializedLabelProgram")]
+pub struct LabelProgram {
+    labels: Vec<Label>,
+    items: ItemTable,
+}
+
+#[derive(Debug, Clone)]
+struct Label {
+    name: String,
+    program: Program,
+    dnf: Dnf,
+}
+
+/// The label definition in the configuration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LabelConfig {
+    pub label: String,
+    pub program: Program,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SerializedLabelProgram {
+    labels: Vec<LabelConfig>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    frames: HashMap<String, FrameConfig>,
+    items: HashMap<String, ItemConfig>,
+}
+
+impl TryFrom<SerializedLabelProgram> for LabelProgram {
+    type Error = anyhow::Error;
+
+    fn try_from(from: SerializedLabelProgram) -> Result<Self, Self::Error> {
+        ensure!(
+            from.labels.len() <= u64::BITS as usize,
+            "at most {} labels are supported",
+            u64::BITS
+        );
+
+        let items = ItemTable::new(from.frames, from.items)?;
+        let labels: Vec<_> = from
+            .labels
+            .into_iter()
+            .map(|LabelConfig { label, program }| -> Result<_> {
+                items.check_program(&program)?;
+                let dnf = Dnf::from_program(program.clone());
+                Ok(Label {
+                    name: label,
+                    program,
+                    dnf,
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self { labels, items })
+    }
+}
+
+impl From<LabelProgram> for SerializedLabelProgram {
+    fn from(from: LabelProgram) -> Self {
+        let LabelProgram { labels, items } = from;
+        let labels = labels
+            .into_iter()
+            .map(|Label { name, program, .. }| LabelConfig {
+                label: name,
+                program,
+            })
+            .collect();
+        let (frames, items) = items.into_parts();
+
+        Self {
+            labels,
+            frames,
+            items,
+        }
+    }
+}
+
+impl LabelProgram {
+    /// The number of labels.
+    pub fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+
+    /// Gets the name of the label at the index.
+    pub fn label(&self, index: usize) -> Option<&str> {
+        Some(&self.labels.get(index)?.name)
+    }
+
+    /// Iterates over label names in the order of priority.
+    pub fn labels(&self) -> impl Iterator<Item = &str> + '_ {
+        self.labels.iter().map(|label| label.name.as_str())
+    }
+
+    /// Returns the index of the first label matching the point.
+    pub fn classify(&self, point: &Point3<f64>, intensity: Option<f64>) -> Option<usize> {
+        self.classify_impl(point, intensity, None)
+    }
+
+    /// Returns the index of the first label matching the point at
+    /// the timestamp in seconds.
+    pub fn classify_at(
+        &self,
+        point: &Point3<f64>,
+        intensity: Option<f64>,
+        time: f64,
+    ) -> Option<usize> {
+        self.classify_impl(point, intensity, Some(time))
+    }
+
+    /// Returns a bitmask where the i-th bit is set if the point
+    /// matches the i-th label.
+    pub fn label_mask(&self, point: &Point3<f64>, intensity: Option<f64>) -> u64 {
+        self.label_mask_impl(point, intensity, None)
+    }
+
+    /// Returns the bitmask of matching labels at the timestamp in
+    /// seconds.
+    pub fn label_mask_at(&self, point: &Point3<f64>, intensity: Option<f64>, time: f64) -> u64 {
+        self.label_mask_impl(point, intensity, Some(time))
+    }
+
+    /// Classifies a batch of points with optional intensities.
+    pub fn classify_batch<I>(&self, points: I) -> Vec<Option<usize>>
+    where
+        I: IntoIterator<Item = (Point3<f64>, Option<f64>)>,
+    {
+        points
+            .into_iter()
+            .map(|(point, intensity)| self.classify(&point, intensity))
+            .collect()
+    }
+
+    /// Computes label bitmasks for a batch of points with optional
+    /// intensities.
+    pub fn label_mask_batch<I>(&self, points: I) -> Vec<u64>
+    where
+        I: IntoIterator<Item = (Point3<f64>, Option<f64>)>,
+    {
+        points
+            .into_iter()
+            .map(|(point, intensity)| self.label_mask(&point, intensity))
+            .collect()
+    }
+
+    fn classify_impl(
+        &self,
+        point: &Point3<f64>,
+        intensity: Option<f64>,
+        time: Option<f64>,
+    ) -> Option<usize> {
+        self.labels
+            .iter()
+            .position(|label| self.items.eval(&label.dnf, point, intensity, time))
+    }
+
+    fn label_mask_impl(
+        &self,
+        point: &Point3<f64>,
+        intensity: Option<f64>,
+        time: Option<f64>,
+    ) -> u64 {
+        self.labels
+            .iter()
+            .enumerate()
+            .filter(|(_, label)| self.items.eval(&label.dnf, point, intensity, time))
+            .fold(0, |mask, (index, _)| mask | (1 << index))
+    }
+}
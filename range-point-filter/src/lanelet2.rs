@@ -0,0 +1,322 @@
+use crate::{
+    filter::SerializedFilterProgram,
+    geodetic::GeodeticOrigin,
+    item::{Item, ItemConfig, PolygonFilterConfig},
+    units, utils, FilterProgram,
+};
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use filter_expr::parser::{parse_str, Program};
+use nalgebra_0_32::Point2;
+use noisy_float::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// The options to convert Lanelet2 maps to items.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Lanelet2Options {
+    /// The origin to project node positions. It is required if nodes
+    /// have no `local_x` and `local_y` tags.
+    #[serde(default)]
+    pub origin: Option<GeodeticOrigin>,
+
+    #[serde(default, deserialize_with = "units::length")]
+    pub z_min: Option<R64>,
+    #[serde(default, deserialize_with = "units::length")]
+    pub z_max: Option<R64>,
+}
+
+/// The regions imported from a Lanelet2 map.
+#[derive(Debug, Clone)]
+pub struct Lanelet2Regions {
+    pub items: HashMap<String, ItemConfig>,
+
+    /// The names of lanelet items, sorted by relation IDs.
+    pub lanelets: Vec<String>,
+
+    /// The names of crosswalk items, sorted by relation IDs.
+    pub crosswalks: Vec<String>,
+
+    /// The names of area items, sorted by relation IDs.
+    pub areas: Vec<String>,
+}
+
+impl Lanelet2Regions {
+    /// The program accepting points on any lanelet or crosswalk.
+    pub fn default_program(&self) -> Result<Program> {
+        let names: Vec<_> = self.lanelets.iter().chain(&self.crosswalks).collect();
+        ensure!(!names.is_empty(), "the map has no lanelets or crosswalks");
+
+        let text = names
+            .iter()
+            .map(|name| name.as_str())
+            .collect::<Vec<_>>()
+            .join(" + ");
+        parse_str(&text)
+    }
+
+    /// Builds a filter program over the imported items.
+    pub fn to_filter_program(&self, program: Program) -> Result<FilterProgram> {
+        SerializedFilterProgram {
+            program,
+            frames: HashMap::new(),
+            items: self.items.clone(),
+        }
+        .try_into()
+    }
+}
+
+/// Loads lanelets, crosswalks and areas from a Lanelet2 map in the
+/// OSM XML format.
+///
+/// Lanelets are converted to polygons enclosed by their left and
+/// right bounds, and multipolygon areas to polygons with holes.
+/// Lanelets and areas with the `crosswalk` subtype are crosswalks.
+/// Items are named `lanelet_<id>`, `crosswalk_<id>` and `area_<id>`
+/// after their relation IDs.
+pub fn from_lanelet2(text: &str, options: &Lanelet2Options) -> Result<Lanelet2Regions> {
+    let doc = roxmltree::Document::parse(text)?;
+    let root = doc.root_element();
+    ensure!(
+        root.has_tag_name("osm"),
+        "the root element must be <osm>, but found <{}>",
+        root.tag_name().name()
+    );
+
+    let mut nodes: HashMap<i64, Point2<f64>> = HashMap::new();
+    let mut ways: HashMap<i64, Vec<i64>> = HashMap::new();
+    let mut relations = vec![];
+
+    for elem in root.children().filter(|node| node.is_element()) {
+        match elem.tag_name().name() {
+            "node" => {
+                let id = parse_attr(&elem, "id")?;
+                let point =
+                    node_position(&elem, options).with_context(|| format!("Invalid node {id}"))?;
+                nodes.insert(id, point);
+            }
+            "way" => {
+                let id = parse_attr(&elem, "id")?;
+                let refs = elem
+                    .children()
+                    .filter(|child| child.has_tag_name("nd"))
+                    .map(|child| parse_attr(&child, "ref"))
+                    .collect::<Result<_>>()?;
+                ways.insert(id, refs);
+            }
+            "relation" => relations.push(elem),
+            _ => {}
+        }
+    }
+
+    let mut regions = Lanelet2Regions {
+        items: HashMap::new(),
+        lanelets: vec![],
+        crosswalks: vec![],
+        areas: vec![],
+    };
+    relations.sort_by_cached_key(|elem| parse_attr::<i64>(elem, "id").ok());
+
+    for elem in relations {
+        let id: i64 = parse_attr(&elem, "id")?;
+        let tags = tags(&elem);
+        let is_crosswalk = tags.get("subtype") == Some(&"crosswalk");
+
+        let members: Vec<(&str, i64)> = elem
+            .children()
+            .filter(|child| child.has_tag_name("member") && child.attribute("type") == Some("way"))
+            .map(|child| {
+                Ok((
+                    child.attribute("role").unwrap_or(""),
+                    parse_attr(&child, "ref")?,
+                ))
+            })
+            .collect::<Result<_>>()?;
+        let way = |id: i64| -> Result<&Vec<i64>> {
+            ways.get(&id)
+                .ok_or_else(|| anyhow!("the way {id} is not defined"))
+        };
+
+        let (mut rings, kind) = match tags.get("type").copied() {
+            Some("lanelet") => {
+                let bound = |role: &str| -> Result<&Vec<i64>> {
+                    let Some(&(_, id)) = members.iter().find(|(r, _)| *r == role) else {
+                        bail!("the lanelet {id} has no {role} bound");
+                    };
+                    way(id)
+                };
+                let left = bound("left")?;
+                let right = bound("right")?;
+                let ring = lanelet_ring(left, right, &nodes)
+                    .with_context(|| format!("Invalid lanelet {id}"))?;
+                (vec![ring], "lanelet")
+            }
+            Some("multipolygon") => {
+                let ways_of = |role: &str| -> Result<Vec<&Vec<i64>>> {
+                    members
+                        .iter()
+                        .filter(|(r, _)| *r == role)
+                        .map(|&(_, id)| way(id))
+                        .collect()
+                };
+                let outer =
+                    join_rings(&ways_of("outer")?).with_context(|| format!("Invalid area {id}"))?;
+                let [outer] = <[_; 1]>::try_from(outer)
+                    .map_err(|_| anyhow!("the area {id} must have exactly one outer ring"))?;
+                let inner =
+                    join_rings(&ways_of("inner")?).with_context(|| format!("Invalid area {id}"))?;
+
+                let rings = std::iter::once(outer)
+                    .chain(inner)
+                    .map(|ring| positions(&ring, &nodes))
+                    .collect::<Result<_>>()?;
+                (rings, "area")
+            }
+            _ => continue,
+        };
+
+        let name = if is_crosswalk {
+            format!("crosswalk_{id}")
+        } else {
+            format!("{kind}_{id}")
+        };
+        let vertices = rings.remove(0);
+        let config = PolygonFilterConfig {
+            vertices: to_coords(&vertices),
+            holes: rings.iter().map(|ring| to_coords(ring)).collect(),
+            z_min: options.z_min,
+            z_max: options.z_max,
+            z: utils::unbounded(),
+        };
+        let item = Item::Polygon(
+            config
+                .try_into()
+                .with_context(|| format!(r#"Unable to build the item "{name}""#))?,
+        );
+
+        if is_crosswalk {
+            regions.crosswalks.push(name.clone());
+        } else if kind == "lanelet" {
+            regions.lanelets.push(name.clone());
+        } else {
+            regions.areas.push(name.clone());
+        }
+        regions.items.insert(name, ItemConfig::from(item));
+    }
+
+    Ok(regions)
+}
+
+fn parse_attr<T>(elem: &roxmltree::Node, name: &str) -> Result<T>
+where
+    T: std::str::FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    let Some(value) = elem.attribute(name) else {
+        bail!(
+            "the attribute {name} is missing in <{}>",
+            elem.tag_name().name()
+        );
+    };
+    value
+        .parse()
+        .with_context(|| format!(r#"Invalid attribute {name}="{value}""#))
+}
+
+fn tags<'a>(elem: &roxmltree::Node<'a, '_>) -> HashMap<&'a str, &'a str> {
+    elem.children()
+        .filter(|child| child.has_tag_name("tag"))
+        .filter_map(|child| Some((child.attribute("k")?, child.attribute("v")?)))
+        .collect()
+}
+
+/// Reads the node position from the `local_x` and `local_y` tags, or
+/// projects its geodetic position by the origin.
+fn node_position(elem: &roxmltree::Node, options: &Lanelet2Options) -> Result<Point2<f64>> {
+    let tags = tags(elem);
+
+    if let (Some(x), Some(y)) = (tags.get("local_x"), tags.get("local_y")) {
+        return Ok(Point2::new(x.parse()?, y.parse()?));
+    }
+
+    let Some(origin) = &options.origin else {
+        bail!("the origin is required for nodes without local coordinates");
+    };
+    let latitude: f64 = parse_attr(elem, "lat")?;
+    let longitude: f64 = parse_attr(elem, "lon")?;
+    let point = origin.project(longitude, latitude, origin.altitude.raw());
+    Ok(Point2::new(point.x, point.y))
+}
+
+fn positions(refs: &[i64], nodes: &HashMap<i64, Point2<f64>>) -> Result<Vec<Point2<f64>>> {
+    refs.iter()
+        .map(|id| {
+            nodes
+                .get(id)
+                .copied()
+                .ok_or_else(|| anyhow!("the node {id} is not defined"))
+        })
+        .collect()
+}
+
+/// Encloses the region between the left and right bounds.
+fn lanelet_ring(
+    left: &[i64],
+    right: &[i64],
+    nodes: &HashMap<i64, Point2<f64>>,
+) -> Result<Vec<Point2<f64>>> {
+    let left = positions(left, nodes)?;
+    let mut right = positions(right, nodes)?;
+    ensure!(
+        !left.is_empty() && !right.is_empty(),
+        "the bounds must not be empty"
+    );
+
+    // Bounds usually follow the driving direction, but they can be
+    // stored inverted in the map.
+    let head = left[0];
+    if (right[0] - head).norm() > (right[right.len() - 1] - head).norm() {
+        right.reverse();
+    }
+
+    Ok(left.into_iter().chain(right.into_iter().rev()).collect())
+}
+
+/// Joins ways into closed rings by matching their end nodes.
+fn join_rings(ways: &[&Vec<i64>]) -> Result<Vec<Vec<i64>>> {
+    let mut remaining: Vec<&[i64]> = ways
+        .iter()
+        .map(|way| way.as_slice())
+        .filter(|way| !way.is_empty())
+        .collect();
+    let mut rings = vec![];
+
+    while let Some(first) = remaining.pop() {
+        let mut ring = first.to_vec();
+
+        while ring.first() != ring.last() || ring.len() == 1 {
+            let tail = *ring.last().unwrap();
+            let Some(index) = remaining
+                .iter()
+                .position(|way| way[0] == tail || way[way.len() - 1] == tail)
+            else {
+                bail!("the ways do not form a closed ring");
+            };
+
+            let way = remaining.swap_remove(index);
+            if way[0] == tail {
+                ring.extend(&way[1..]);
+            } else {
+                ring.extend(way.iter().rev().skip(1));
+            }
+        }
+
+        ring.pop();
+        rings.push(ring);
+    }
+
+    Ok(rings)
+}
+
+fn to_coords(ring: &[Point2<f64>]) -> Vec<[R64; 2]> {
+    ring.iter().map(|p| [r64(p.x), r64(p.y)]).collect()
+}
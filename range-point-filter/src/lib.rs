@@ -1,7 +1,22 @@
+pub mod bounds;
+pub mod explain;
 pub mod filter;
+pub mod frame;
+pub mod geodetic;
 pub mod item;
-// mod utils;
+pub mod label;
+pub mod lanelet2;
+pub mod lint;
+pub mod measure;
+pub mod mesh;
+pub mod raster;
+pub mod schedule;
+pub mod units;
+mod utils;
 #[cfg(feature = "with-kiss3d")]
 mod with_kiss3d;
 
+pub use bounds::Aabb;
 pub use filter::FilterProgram;
+pub use label::LabelProgram;
+pub use raster::RasterProgram;
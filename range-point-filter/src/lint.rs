@@ -0,0 +1,182 @@
+use crate::{bounds::Aabb, item::Item, FilterProgram};
+use filter_expr::parser::{Expr, ExprBinOp};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::{self, Display, Formatter},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// The config works, but likely not as intended.
+    Warning,
+    /// A part of the config can never accept any point.
+    Error,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// The part of the config where a problem is found.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Location {
+    /// The item with the name.
+    Item(String),
+    /// The product at the index in the disjunctive normal form of
+    /// the program, along with its text.
+    Product { index: usize, text: String },
+}
+
+impl Display for Location {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Location::Item(name) => write!(f, r#"item "{name}""#),
+            Location::Product { index, text } => write!(f, "product [{index}] {text}"),
+        }
+    }
+}
+
+/// A problem found in the config.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Finding {
+    pub severity: Severity,
+    pub location: Location,
+    pub message: String,
+}
+
+impl Display for Finding {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let Self {
+            severity,
+            location,
+            message,
+        } = self;
+        write!(f, "{severity}: {location}: {message}")
+    }
+}
+
+impl FilterProgram {
+    /// Reports problems in the config which are accepted by the
+    /// parser but likely mistakes.
+    pub fn lint(&self) -> Vec<Finding> {
+        let mut findings = vec![];
+        let item_finding = |severity, name: &str, message: String| Finding {
+            severity,
+            location: Location::Item(name.to_string()),
+            message,
+        };
+
+        // Check items in the order of names.
+        let mut referred = BTreeSet::new();
+        collect_idents(&self.program.0, &mut referred);
+        let names: BTreeSet<&str> = self.items.names().collect();
+
+        for &name in &names {
+            if !referred.contains(name) {
+                findings.push(item_finding(
+                    Severity::Warning,
+                    name,
+                    "defined but never referenced in the program".to_string(),
+                ));
+            }
+
+            match self.items.get(name) {
+                Item::Box(filter) => {
+                    let [rx, ry] = &filter.ranges;
+                    let [w, h] = [rx, ry].map(|r| r.end() - r.start());
+                    if w < 0.0 || h < 0.0 {
+                        findings.push(item_finding(
+                            Severity::Error,
+                            name,
+                            format!("the box size [{w}, {h}] is negative"),
+                        ));
+                    } else if w == 0.0 || h == 0.0 {
+                        findings.push(item_finding(
+                            Severity::Warning,
+                            name,
+                            format!("the box size [{w}, {h}] is degenerate"),
+                        ));
+                    }
+                }
+                Item::Intensity(filter) => {
+                    if filter.is_empty() {
+                        findings.push(item_finding(
+                            Severity::Error,
+                            name,
+                            "the intensity range is empty".to_string(),
+                        ));
+                    }
+                }
+                Item::Polygon(_) | Item::Corridor(_) => {}
+            }
+        }
+
+        // Find products which can never be true, and collect the
+        // positive bounds of products where items are negated.
+        let mut negations: BTreeMap<&str, Vec<Option<Aabb>>> = BTreeMap::new();
+
+        for (index, product) in self.dnf.0.iter().enumerate() {
+            let positive = product
+                .0
+                .iter()
+                .filter(|term| !term.invert)
+                .filter_map(|term| self.items.get(&term.ident.0).bounds())
+                .reduce(|lhs, rhs| lhs.intersection(&rhs));
+
+            if positive.is_some_and(|bounds| bounds.is_empty()) {
+                findings.push(Finding {
+                    severity: Severity::Warning,
+                    location: Location::Product {
+                        index,
+                        text: product.to_string(),
+                    },
+                    message: "the product can never be true since its items are disjoint"
+                        .to_string(),
+                });
+                continue;
+            }
+
+            for term in product.0.iter().filter(|term| term.invert) {
+                negations.entry(&term.ident.0).or_default().push(positive);
+            }
+        }
+
+        for (name, positives) in negations {
+            let Some(bounds) = self.items.get(name).bounds() else {
+                continue;
+            };
+            let no_effect = positives.iter().all(|positive| {
+                positive.is_some_and(|positive| positive.intersection(&bounds).is_empty())
+            });
+
+            if no_effect {
+                findings.push(item_finding(
+                    Severity::Warning,
+                    name,
+                    "the negated item lies outside the accepted region and has no effect"
+                        .to_string(),
+                ));
+            }
+        }
+
+        findings
+    }
+}
+
+fn collect_idents<'a>(expr: &'a Expr, idents: &mut BTreeSet<&'a str>) {
+    match expr {
+        Expr::Ident(ident) => {
+            idents.insert(&ident.0);
+        }
+        Expr::UnaryOp(expr) => collect_idents(expr, idents),
+        Expr::BinOp(ExprBinOp { lhs, rhs, .. }) => {
+            collect_idents(lhs, idents);
+            collect_idents(rhs, idents);
+        }
+    }
+}
@@ -0,0 +1,357 @@
+use crate::{bounds::Aabb, item::Item, FilterProgram};
+use anyhow::{ensure, Result};
+use filter_expr::normal_form::Term;
+use nalgebra_0_32::{Point2, Point3};
+use rand::prelude::*;
+use rand_chacha::ChaCha8Rng;
+use std::ops::RangeInclusive;
+
+/// The maximum number of products for exact computation. The
+/// inclusion–exclusion over products grows exponentially.
+const MAX_EXACT_PRODUCTS: usize = 16;
+
+/// The minimum and maximum number of stratified batches in Monte
+/// Carlo estimation.
+const MIN_BATCHES: usize = 8;
+const MAX_BATCHES: usize = 4096;
+
+/// The number of strata per axis in each batch.
+const STRATA_3D: usize = 8;
+const STRATA_2D: usize = 16;
+
+/// The estimated area or volume of a region.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Estimate {
+    /// The area in square meters or the volume in cubic meters.
+    pub value: f64,
+
+    /// The standard error of the value, which is zero if the value is
+    /// exact.
+    pub std_error: f64,
+
+    /// The number of sampled points, which is zero if the value is
+    /// exact.
+    pub samples: usize,
+}
+
+impl Estimate {
+    pub fn is_exact(&self) -> bool {
+        self.samples == 0
+    }
+}
+
+/// A vertical prism with a convex footprint.
+#[derive(Debug, Clone)]
+struct Prism {
+    /// The footprint in counter-clockwise order.
+    polygon: Vec<Point2<f64>>,
+    z_range: RangeInclusive<f64>,
+}
+
+impl Prism {
+    fn new(
+        x_range: &RangeInclusive<f64>,
+        y_range: &RangeInclusive<f64>,
+        z_range: RangeInclusive<f64>,
+    ) -> Self {
+        let polygon = vec![
+            Point2::new(*x_range.start(), *y_range.start()),
+            Point2::new(*x_range.end(), *y_range.start()),
+            Point2::new(*x_range.end(), *y_range.end()),
+            Point2::new(*x_range.start(), *y_range.end()),
+        ];
+        Self { polygon, z_range }
+    }
+
+    fn intersection(&self, other: &Self) -> Option<Self> {
+        let z_start = self.z_range.start().max(*other.z_range.start());
+        let z_end = self.z_range.end().min(*other.z_range.end());
+        if z_start > z_end {
+            return None;
+        }
+
+        let polygon = clip_polygon(&self.polygon, &other.polygon);
+        if polygon.len() < 3 {
+            return None;
+        }
+
+        Some(Self {
+            polygon,
+            z_range: z_start..=z_end,
+        })
+    }
+
+    fn area(&self) -> f64 {
+        polygon_area(&self.polygon)
+    }
+
+    fn volume(&self) -> f64 {
+        self.area() * (self.z_range.end() - self.z_range.start())
+    }
+}
+
+impl FilterProgram {
+    /// Estimates the volume accepted by the program within the
+    /// bounds, where intensity items accept all points.
+    ///
+    /// The volume is exact if the program is a union of intersected
+    /// boxes and intensity items. Otherwise, it is estimated by
+    /// stratified Monte Carlo sampling until the standard error falls
+    /// below the tolerance. The sampling is reproducible from the
+    /// seed.
+    pub fn estimate_volume(&self, bounds: &Aabb, tolerance: f64, seed: u64) -> Result<Estimate> {
+        ensure!(
+            !bounds.is_empty()
+                && (0..3).all(|i| bounds.min[i].is_finite() && bounds.max[i].is_finite()),
+            "the bounds must be finite and non-empty"
+        );
+        ensure!(tolerance > 0.0, "the tolerance must be positive");
+
+        let x_range = bounds.min.x..=bounds.max.x;
+        let y_range = bounds.min.y..=bounds.max.y;
+        let z_range = bounds.min.z..=bounds.max.z;
+        let clip = Prism::new(&x_range, &y_range, z_range.clone());
+
+        if let Some(prisms) = self.exact_prisms(&clip) {
+            let value = union_measure(&prisms, Prism::volume);
+            return Ok(Estimate {
+                value,
+                std_error: 0.0,
+                samples: 0,
+            });
+        }
+
+        let ranges = [x_range, y_range, z_range];
+        let strata = [STRATA_3D; 3];
+        let estimate = self.monte_carlo(&ranges, strata, tolerance, seed, |[x, y, z]| {
+            Point3::new(x, y, z)
+        });
+        Ok(estimate)
+    }
+
+    /// Estimates the area of the horizontal cross section at height
+    /// `z` accepted by the program within the bounds, where intensity
+    /// items accept all points.
+    ///
+    /// The area is computed in the same way as
+    /// [`estimate_volume`](Self::estimate_volume).
+    pub fn estimate_area(
+        &self,
+        bounds: [RangeInclusive<f64>; 2],
+        z: f64,
+        tolerance: f64,
+        seed: u64,
+    ) -> Result<Estimate> {
+        ensure!(
+            bounds
+                .iter()
+                .all(|r| r.start().is_finite() && r.end().is_finite() && r.start() <= r.end()),
+            "the bounds must be finite and non-empty"
+        );
+        ensure!(tolerance > 0.0, "the tolerance must be positive");
+
+        let [x_range, y_range] = bounds;
+        let clip = Prism::new(&x_range, &y_range, z..=z);
+
+        if let Some(prisms) = self.exact_prisms(&clip) {
+            let value = union_measure(&prisms, Prism::area);
+            return Ok(Estimate {
+                value,
+                std_error: 0.0,
+                samples: 0,
+            });
+        }
+
+        let ranges = [x_range, y_range];
+        let strata = [STRATA_2D; 2];
+        let estimate = self.monte_carlo(&ranges, strata, tolerance, seed, |[x, y]| {
+            Point3::new(x, y, z)
+        });
+        Ok(estimate)
+    }
+
+    /// Converts every product to a prism clipped by the bounds, or
+    /// returns `None` if the program is not a union of intersected
+    /// boxes.
+    fn exact_prisms(&self, clip: &Prism) -> Option<Vec<Prism>> {
+        if self.dnf.0.len() > MAX_EXACT_PRODUCTS {
+            return None;
+        }
+
+        let mut prisms = vec![];
+
+        'product: for product in &self.dnf.0 {
+            let mut prism = clip.clone();
+
+            for term in &product.0 {
+                let Term { ref ident, invert } = *term;
+
+                match (self.items.get(&ident.0), invert) {
+                    (Item::Box(_), true) => return None,
+                    // Boxes with negative extents accept no points,
+                    // although their corners span a nonzero area.
+                    (Item::Box(filter), false) if filter.ranges.iter().any(|r| r.is_empty()) => {
+                        continue 'product;
+                    }
+                    (Item::Box(filter), false) => {
+                        // The interval ends have no volume, so whether
+                        // they are inclusive does not matter.
+                        let z_range = &filter.z_range;
+                        let other = Prism {
+                            polygon: filter.corners_2d().to_vec(),
+                            z_range: z_range.start()..=z_range.end(),
+                        };
+
+                        match prism.intersection(&other) {
+                            Some(next) => prism = next,
+                            None => continue 'product,
+                        }
+                    }
+                    // Polygons can be concave and corridors are
+                    // curved.
+                    (Item::Polygon(_) | Item::Corridor(_), _) => return None,
+                    // Intensity items accept all points without
+                    // intensities.
+                    (Item::Intensity(_), false) => {}
+                    (Item::Intensity(_), true) => continue 'product,
+                }
+            }
+
+            prisms.push(prism);
+        }
+
+        Some(prisms)
+    }
+
+    fn monte_carlo<const N: usize>(
+        &self,
+        ranges: &[RangeInclusive<f64>; N],
+        strata: [usize; N],
+        tolerance: f64,
+        seed: u64,
+        to_point: impl Fn([f64; N]) -> Point3<f64>,
+    ) -> Estimate {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let measure: f64 = ranges.iter().map(|r| r.end() - r.start()).product();
+        let batch_size: usize = strata.iter().product();
+
+        let mut sum = 0.0;
+        let mut sum_sq = 0.0;
+        let mut num_batches = 0;
+
+        let std_error = loop {
+            let mut hits = 0;
+
+            for index in 0..batch_size {
+                // Draw one point within each stratum.
+                let mut rem = index;
+                let coords: [f64; N] = std::array::from_fn(|axis| {
+                    let n = strata[axis];
+                    let cell = rem % n;
+                    rem /= n;
+
+                    let range = &ranges[axis];
+                    let step = (range.end() - range.start()) / n as f64;
+                    range.start() + step * (cell as f64 + rng.gen::<f64>())
+                });
+
+                if self.contains(&to_point(coords), None) {
+                    hits += 1;
+                }
+            }
+
+            let value = measure * hits as f64 / batch_size as f64;
+            sum += value;
+            sum_sq += value * value;
+            num_batches += 1;
+
+            if num_batches >= MIN_BATCHES {
+                let n = num_batches as f64;
+                let mean = sum / n;
+                let var = ((sum_sq - n * mean * mean) / (n - 1.0)).max(0.0);
+                let std_error = (var / n).sqrt();
+
+                if std_error <= tolerance || num_batches >= MAX_BATCHES {
+                    break std_error;
+                }
+            }
+        };
+
+        Estimate {
+            value: sum / num_batches as f64,
+            std_error,
+            samples: num_batches * batch_size,
+        }
+    }
+}
+
+/// Computes the measure of the union of prisms by inclusion–exclusion.
+fn union_measure(prisms: &[Prism], measure: fn(&Prism) -> f64) -> f64 {
+    fn recurse(
+        prisms: &[Prism],
+        start: usize,
+        current: &Prism,
+        sign: f64,
+        measure: fn(&Prism) -> f64,
+    ) -> f64 {
+        let mut total = sign * measure(current);
+
+        for (index, prism) in prisms.iter().enumerate().skip(start) {
+            // Intersections with an empty set are skipped since their
+            // supersets are empty as well.
+            if let Some(next) = current.intersection(prism) {
+                total += recurse(prisms, index + 1, &next, -sign, measure);
+            }
+        }
+
+        total
+    }
+
+    prisms
+        .iter()
+        .enumerate()
+        .map(|(index, prism)| recurse(prisms, index + 1, prism, 1.0, measure))
+        .sum()
+}
+
+/// Clips a convex polygon by another convex polygon using the
+/// Sutherland–Hodgman algorithm. Both are in counter-clockwise order.
+fn clip_polygon(subject: &[Point2<f64>], clip: &[Point2<f64>]) -> Vec<Point2<f64>> {
+    let mut output = subject.to_vec();
+
+    for (index, &a) in clip.iter().enumerate() {
+        let b = clip[(index + 1) % clip.len()];
+        let edge = b - a;
+        let side = |p: &Point2<f64>| edge.perp(&(p - a));
+
+        let input = std::mem::take(&mut output);
+        for (index, p) in input.iter().enumerate() {
+            let q = &input[(index + 1) % input.len()];
+            let (sp, sq) = (side(p), side(q));
+
+            if sp >= 0.0 {
+                output.push(*p);
+            }
+            if (sp >= 0.0) != (sq >= 0.0) {
+                let ratio = sp / (sp - sq);
+                output.push(p + (q - p) * ratio);
+            }
+        }
+
+        if output.is_empty() {
+            break;
+        }
+    }
+
+    output
+}
+
+/// Computes the area of a polygon by the shoelace formula.
+fn polygon_area(polygon: &[Point2<f64>]) -> f64 {
+    let twice: f64 = polygon
+        .iter()
+        .zip(polygon.iter().cycle().skip(1))
+        .map(|(p, q)| p.x * q.y - q.x * p.y)
+        .sum();
+    twice.abs() / 2.0
+}
@@ -0,0 +1,613 @@
+use crate::{item::Item, utils::Interval, FilterProgram};
+use anyhow::{bail, ensure, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use nalgebra_0_32::{Point2, Point3, Vector2};
+use serde_json::json;
+use std::{
+    collections::BTreeMap,
+    f64::consts::TAU,
+    fs::{self, File},
+    io::{BufWriter, Write},
+    ops::Range,
+    path::Path,
+};
+
+/// The number of segments approximating the round ends and joints
+/// of corridors.
+const ROUND_SEGMENTS: usize = 16;
+
+/// The RGBA colors of included and excluded items.
+const INCLUDED_COLOR: [f64; 4] = [0.0, 0.8, 0.0, 0.5];
+const EXCLUDED_COLOR: [f64; 4] = [0.8, 0.0, 0.0, 0.5];
+
+/// The mesh file formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MeshFormat {
+    /// Wavefront OBJ along with a MTL material file.
+    Obj,
+    /// ASCII PLY with vertex colors.
+    Ply,
+    /// glTF 2.0 with the embedded buffer.
+    Gltf,
+}
+
+impl MeshFormat {
+    /// Guesses the format from the file extension.
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Self> {
+        let ext = path.as_ref().extension()?.to_str()?.to_ascii_lowercase();
+        let format = match ext.as_str() {
+            "obj" => Self::Obj,
+            "ply" => Self::Ply,
+            "gltf" => Self::Gltf,
+            _ => return None,
+        };
+        Some(format)
+    }
+}
+
+struct ItemMesh<'a> {
+    name: &'a str,
+    excluded: bool,
+    vertices: Vec<Point3<f64>>,
+    triangles: Vec<[u32; 3]>,
+}
+
+impl ItemMesh<'_> {
+    /// Adds the prism over the polygon with holes between the z
+    /// limits. The vertices are the bottom vertices of all rings
+    /// followed by the top ones.
+    fn extrude(
+        &mut self,
+        outer: &[Point2<f64>],
+        holes: &[Vec<Point2<f64>>],
+        [z_min, z_max]: [f64; 2],
+    ) {
+        // Orient the boundary counter-clockwise and holes clockwise,
+        // so that faces point outwards.
+        let rings: Vec<Vec<Point2<f64>>> = std::iter::once(oriented(outer, true))
+            .chain(holes.iter().map(|hole| oriented(hole, false)))
+            .collect();
+
+        let offset = self.vertices.len() as u32;
+        let len = rings.iter().map(|ring| ring.len()).sum::<usize>() as u32;
+        for z in [z_min, z_max] {
+            let vertices = rings.iter().flatten().map(|p| Point3::new(p.x, p.y, z));
+            self.vertices.extend(vertices);
+        }
+
+        for [a, b, c] in triangulate(&rings) {
+            self.triangles.push([a, c, b].map(|v| v + offset));
+            self.triangles.push([a, b, c].map(|v| v + offset + len));
+        }
+
+        let mut start = offset;
+        for ring in &rings {
+            let ring_len = ring.len() as u32;
+            for index in 0..ring_len {
+                let a = start + index;
+                let b = start + (index + 1) % ring_len;
+                self.triangles.push([a, b, b + len]);
+                self.triangles.push([a, b + len, a + len]);
+            }
+            start += ring_len;
+        }
+    }
+}
+
+impl FilterProgram {
+    /// Writes the geometry of box, polygon and corridor items referred
+    /// by the program to a mesh file, in which the meshes are named
+    /// after items.
+    ///
+    /// Items which only appear negated in the program are drawn in
+    /// the excluded material. Items unbounded along the z axis are
+    /// extruded to `height` from the finite side, or from zero if
+    /// both sides are unbounded. Corridors are drawn as the union of
+    /// their segments and round joints. Items accepting no points,
+    /// such as boxes with negative extents, are skipped.
+    ///
+    /// The output path and the extrusion height are taken along with
+    /// the format, since OBJ files refer to a MTL file next to them
+    /// and planar items have no height of their own.
+    pub fn export_mesh(
+        &self,
+        path: impl AsRef<Path>,
+        format: MeshFormat,
+        height: f64,
+    ) -> Result<()> {
+        ensure!(height > 0.0, "the extrusion height must be positive");
+
+        let meshes = self.item_meshes(height);
+        if meshes.is_empty() {
+            bail!("the program has no region items to export");
+        }
+
+        let path = path.as_ref();
+        match format {
+            MeshFormat::Obj => write_obj(path, &meshes),
+            MeshFormat::Ply => write_ply(path, &meshes),
+            MeshFormat::Gltf => write_gltf(path, &meshes),
+        }
+    }
+
+    fn item_meshes(&self, height: f64) -> Vec<ItemMesh<'_>> {
+        // Collect the polarity of referred items, sorted by names.
+        let mut excluded: BTreeMap<&str, bool> = BTreeMap::new();
+        for term in self.dnf.0.iter().flat_map(|product| &product.0) {
+            *excluded.entry(&term.ident.0).or_insert(true) &= term.invert;
+        }
+
+        excluded
+            .into_iter()
+            .filter_map(|(name, excluded)| {
+                let mut mesh = ItemMesh {
+                    name,
+                    excluded,
+                    vertices: vec![],
+                    triangles: vec![],
+                };
+
+                match self.items.get(name) {
+                    // Boxes with negative extents accept no points.
+                    Item::Box(filter) if filter.ranges.iter().any(|r| r.is_empty()) => {
+                        return None;
+                    }
+                    Item::Box(filter) => {
+                        let z_limits = extrusion(&filter.z_range, height);
+                        mesh.extrude(&filter.corners_2d(), &[], z_limits);
+                    }
+                    Item::Polygon(filter) => {
+                        let z_limits = extrusion(&filter.z_range, height);
+                        mesh.extrude(&filter.vertices, &filter.holes, z_limits);
+                    }
+                    Item::Corridor(filter) => {
+                        let z_limits = extrusion(&filter.z_range, height);
+                        let radius = filter.width / 2.0;
+                        if radius <= 0.0 {
+                            return None;
+                        }
+
+                        for segment in filter.path.windows(2) {
+                            let [a, b] = [segment[0], segment[1]];
+                            let Some(dir) = (b - a).try_normalize(0.0) else {
+                                continue;
+                            };
+                            let side = Vector2::new(-dir.y, dir.x) * radius;
+                            mesh.extrude(&[a - side, b - side, b + side, a + side], &[], z_limits);
+                        }
+                        for center in &filter.path {
+                            let disc: Vec<_> = (0..ROUND_SEGMENTS)
+                                .map(|index| {
+                                    let angle = TAU * index as f64 / ROUND_SEGMENTS as f64;
+                                    center + Vector2::new(angle.cos(), angle.sin()) * radius
+                                })
+                                .collect();
+                            mesh.extrude(&disc, &[], z_limits);
+                        }
+                    }
+                    Item::Intensity(_) => return None,
+                }
+
+                // Skip empty meshes, which would make empty glTF
+                // primitives.
+                (!mesh.triangles.is_empty()).then_some(mesh)
+            })
+            .collect()
+    }
+}
+
+/// The z limits to extrude an item, where unbounded sides are
+/// limited to `height` from the other side.
+fn extrusion(z_range: &Interval, height: f64) -> [f64; 2] {
+    let (start, end) = (z_range.start(), z_range.end());
+    match (start.is_finite(), end.is_finite()) {
+        (true, true) => [start, end],
+        (true, false) => [start, start + height],
+        (false, true) => [end - height, end],
+        (false, false) => [0.0, height],
+    }
+}
+
+/// Twice the signed area of the ring, which is positive if the ring
+/// is counter-clockwise.
+fn signed_area(ring: &[Point2<f64>]) -> f64 {
+    (0..ring.len())
+        .map(|index| {
+            let [a, b] = [ring[index], ring[(index + 1) % ring.len()]];
+            a.x * b.y - b.x * a.y
+        })
+        .sum()
+}
+
+fn oriented(ring: &[Point2<f64>], ccw: bool) -> Vec<Point2<f64>> {
+    let mut ring = ring.to_vec();
+    if (signed_area(&ring) > 0.0) != ccw {
+        ring.reverse();
+    }
+    ring
+}
+
+fn cross(o: Point2<f64>, a: Point2<f64>, b: Point2<f64>) -> f64 {
+    (a - o).perp(&(b - o))
+}
+
+/// Triangulates the polygon by ear clipping, where the first ring is
+/// the counter-clockwise boundary and the rest are clockwise holes.
+/// Indices refer to the vertices of all rings in order.
+fn triangulate(rings: &[Vec<Point2<f64>>]) -> Vec<[u32; 3]> {
+    let points: Vec<Point2<f64>> = rings.concat();
+    let mut ranges: Vec<Range<usize>> = vec![];
+    for ring in rings {
+        let start = ranges.last().map_or(0, |range| range.end);
+        ranges.push(start..start + ring.len());
+    }
+
+    // Bridge holes into the boundary, starting from the rightmost
+    // hole, so that the polygon becomes a single ring.
+    let mut polygon: Vec<usize> = ranges[0].clone().collect();
+    let mut holes = ranges.split_off(1);
+    let max_x = |range: &Range<usize>| range.clone().map(|v| points[v].x).fold(f64::MIN, f64::max);
+    holes.sort_by(|lhs, rhs| max_x(rhs).total_cmp(&max_x(lhs)));
+
+    for (nth, hole) in holes.iter().enumerate() {
+        let Some(from) = hole
+            .clone()
+            .max_by(|&a, &b| points[a].x.total_cmp(&points[b].x))
+        else {
+            continue;
+        };
+        let others = &holes[nth..];
+        let Some(pos) = bridge(&points, &polygon, others, from) else {
+            continue;
+        };
+
+        // Walk around the hole from the bridge vertex and return.
+        let len = hole.len();
+        let walk = (0..=len).map(|step| hole.start + (from - hole.start + step) % len);
+        let to = polygon[pos];
+        let tail = polygon.split_off(pos + 1);
+        polygon.extend(walk);
+        polygon.push(to);
+        polygon.extend(tail);
+    }
+
+    let mut triangles = vec![];
+    while polygon.len() > 3 {
+        let len = polygon.len();
+        let corner = |pos: usize| [len - 1, 0, 1].map(|delta| points[polygon[(pos + delta) % len]]);
+
+        let ear = (0..len).find(|&pos| {
+            let [a, b, c] = corner(pos);
+            cross(a, b, c) > 0.0
+                && polygon.iter().all(|&v| {
+                    let p = points[v];
+                    p == a
+                        || p == b
+                        || p == c
+                        || !(cross(a, b, p) >= 0.0
+                            && cross(b, c, p) >= 0.0
+                            && cross(c, a, p) >= 0.0)
+                })
+        });
+
+        match ear {
+            Some(pos) => {
+                let [a, b, c] = [len - 1, 0, 1].map(|delta| polygon[(pos + delta) % len] as u32);
+                triangles.push([a, b, c]);
+                polygon.remove(pos);
+            }
+            None => {
+                // Degenerate polygons have no ears. Clip a flat
+                // vertex if any, then a convex one, so that it
+                // terminates without leaving holes in the cap.
+                let pos = (0..len)
+                    .find(|&pos| {
+                        let [a, b, c] = corner(pos);
+                        cross(a, b, c) == 0.0
+                    })
+                    .or_else(|| {
+                        (0..len).find(|&pos| {
+                            let [a, b, c] = corner(pos);
+                            cross(a, b, c) > 0.0
+                        })
+                    })
+                    .unwrap_or(0);
+                let [a, b, c] = [len - 1, 0, 1].map(|delta| polygon[(pos + delta) % len] as u32);
+                triangles.push([a, b, c]);
+                polygon.remove(pos);
+            }
+        }
+    }
+    if let [a, b, c] = polygon[..] {
+        triangles.push([a, b, c].map(|v| v as u32));
+    }
+
+    triangles
+}
+
+/// Finds the position of the nearest polygon vertex which the hole
+/// vertex can be connected to without crossing any edge of the
+/// polygon or the holes.
+fn bridge(
+    points: &[Point2<f64>],
+    polygon: &[usize],
+    holes: &[Range<usize>],
+    from: usize,
+) -> Option<usize> {
+    let origin = points[from];
+    let ring_edges = |range: &Range<usize>| {
+        let range = range.clone();
+        range
+            .clone()
+            .map(move |v| [v, range.start + (v - range.start + 1) % range.len()])
+    };
+    let edges: Vec<[usize; 2]> = (0..polygon.len())
+        .map(|pos| [polygon[pos], polygon[(pos + 1) % polygon.len()]])
+        .chain(holes.iter().flat_map(ring_edges))
+        .collect();
+
+    let len = polygon.len();
+    (0..len)
+        .filter(|&pos| {
+            let [prev, to, next] =
+                [len - 1, 0, 1].map(|delta| points[polygon[(pos + delta) % len]]);
+
+            // The bridge must leave the vertex into the interior.
+            let inside = if cross(prev, to, next) > 0.0 {
+                cross(to, next, origin) > 0.0 && cross(prev, to, origin) > 0.0
+            } else {
+                cross(to, next, origin) > 0.0 || cross(prev, to, origin) > 0.0
+            };
+
+            inside
+                && edges.iter().all(|&[a, b]| {
+                    let [a, b] = [points[a], points[b]];
+                    [a, b].contains(&to)
+                        || [a, b].contains(&origin)
+                        || !segments_cross(origin, to, a, b)
+                })
+        })
+        .min_by(|&lhs, &rhs| {
+            let dist = |pos: usize| (points[polygon[pos]] - origin).norm_squared();
+            dist(lhs).total_cmp(&dist(rhs))
+        })
+}
+
+/// Checks if two segments intersect, including touching.
+fn segments_cross(p1: Point2<f64>, p2: Point2<f64>, q1: Point2<f64>, q2: Point2<f64>) -> bool {
+    let [d1, d2] = [cross(q1, q2, p1), cross(q1, q2, p2)];
+    let [d3, d4] = [cross(p1, p2, q1), cross(p1, p2, q2)];
+    d1 * d2 <= 0.0 && d3 * d4 <= 0.0 && !(d1 == 0.0 && d2 == 0.0 && d3 == 0.0 && d4 == 0.0)
+}
+
+fn material_name(excluded: bool) -> &'static str {
+    if excluded {
+        "excluded"
+    } else {
+        "included"
+    }
+}
+
+fn material_color(excluded: bool) -> [f64; 4] {
+    if excluded {
+        EXCLUDED_COLOR
+    } else {
+        INCLUDED_COLOR
+    }
+}
+
+fn write_obj(path: &Path, meshes: &[ItemMesh]) -> Result<()> {
+    let mtl_path = path.with_extension("mtl");
+    let Some(mtl_name) = mtl_path.file_name().and_then(|name| name.to_str()) else {
+        bail!("invalid file name {}", path.display());
+    };
+
+    let mut writer = BufWriter::new(File::create(path)?);
+    writeln!(writer, "mtllib {mtl_name}")?;
+
+    // OBJ indices are 1-based and global.
+    let mut offset = 1;
+    for mesh in meshes {
+        writeln!(writer, "g {}", mesh.name)?;
+        writeln!(writer, "usemtl {}", material_name(mesh.excluded))?;
+
+        for p in &mesh.vertices {
+            writeln!(writer, "v {} {} {}", p.x, p.y, p.z)?;
+        }
+        for triangle in &mesh.triangles {
+            let [a, b, c] = triangle.map(|v| v as usize + offset);
+            writeln!(writer, "f {a} {b} {c}")?;
+        }
+        offset += mesh.vertices.len();
+    }
+    writer.flush()?;
+
+    let mut writer = BufWriter::new(File::create(&mtl_path)?);
+    for excluded in [false, true] {
+        let [r, g, b, a] = material_color(excluded);
+        writeln!(writer, "newmtl {}", material_name(excluded))?;
+        writeln!(writer, "Kd {r} {g} {b}")?;
+        writeln!(writer, "d {a}")?;
+    }
+    writer.flush()?;
+
+    Ok(())
+}
+
+fn write_ply(path: &Path, meshes: &[ItemMesh]) -> Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+
+    writeln!(writer, "ply")?;
+    writeln!(writer, "format ascii 1.0")?;
+    // PLY has no groups. The vertex ranges of items are listed in
+    // comments instead.
+    let mut start = 0;
+    for mesh in meshes {
+        let end = start + mesh.vertices.len();
+        let material = material_name(mesh.excluded);
+        writeln!(
+            writer,
+            "comment item {} {material} vertices {start}..{end}",
+            mesh.name
+        )?;
+        start = end;
+    }
+    let vertices: usize = meshes.iter().map(|mesh| mesh.vertices.len()).sum();
+    let triangles: usize = meshes.iter().map(|mesh| mesh.triangles.len()).sum();
+    writeln!(writer, "element vertex {vertices}")?;
+    writeln!(writer, "property float x")?;
+    writeln!(writer, "property float y")?;
+    writeln!(writer, "property float z")?;
+    writeln!(writer, "property uchar red")?;
+    writeln!(writer, "property uchar green")?;
+    writeln!(writer, "property uchar blue")?;
+    writeln!(writer, "property uchar alpha")?;
+    writeln!(writer, "element face {triangles}")?;
+    writeln!(writer, "property list uchar int vertex_indices")?;
+    writeln!(writer, "end_header")?;
+
+    for mesh in meshes {
+        let [r, g, b, a] = material_color(mesh.excluded).map(|v| (v * 255.0).round() as u8);
+        for p in &mesh.vertices {
+            writeln!(writer, "{} {} {} {r} {g} {b} {a}", p.x, p.y, p.z)?;
+        }
+    }
+
+    let mut offset = 0;
+    for mesh in meshes {
+        for triangle in &mesh.triangles {
+            let [a, b, c] = triangle.map(|v| v as usize + offset);
+            writeln!(writer, "3 {a} {b} {c}")?;
+        }
+        offset += mesh.vertices.len();
+    }
+    writer.flush()?;
+
+    Ok(())
+}
+
+fn write_gltf(path: &Path, meshes: &[ItemMesh]) -> Result<()> {
+    // The buffer stores positions of all items followed by the
+    // indices of all items.
+    let mut buffer = vec![];
+    for mesh in meshes {
+        for p in &mesh.vertices {
+            for v in [p.x, p.y, p.z] {
+                buffer.extend((v as f32).to_le_bytes());
+            }
+        }
+    }
+    let index_offset = buffer.len();
+    for mesh in meshes {
+        for v in mesh.triangles.iter().flatten() {
+            buffer.extend(v.to_le_bytes());
+        }
+    }
+    let index_size = buffer.len() - index_offset;
+
+    let mut accessors = vec![];
+    let buffer_views = vec![
+        json!({
+            "buffer": 0,
+            "byteOffset": 0,
+            "byteLength": index_offset,
+            "byteStride": 12,
+            "target": 34962,
+        }),
+        json!({
+            "buffer": 0,
+            "byteOffset": index_offset,
+            "byteLength": index_size,
+            "target": 34963,
+        }),
+    ];
+
+    let mut gltf_meshes = vec![];
+    let mut nodes = vec![];
+
+    let (mut position_start, mut index_start) = (0, 0);
+    for (index, mesh) in meshes.iter().enumerate() {
+        let (min, max) = mesh.vertices.iter().fold(
+            ([f64::INFINITY; 3], [f64::NEG_INFINITY; 3]),
+            |(min, max), p| {
+                (
+                    [min[0].min(p.x), min[1].min(p.y), min[2].min(p.z)],
+                    [max[0].max(p.x), max[1].max(p.y), max[2].max(p.z)],
+                )
+            },
+        );
+
+        accessors.push(json!({
+            "bufferView": 0,
+            "byteOffset": position_start,
+            "componentType": 5126,
+            "count": mesh.vertices.len(),
+            "type": "VEC3",
+            "min": min.map(|v| v as f32),
+            "max": max.map(|v| v as f32),
+        }));
+        accessors.push(json!({
+            "bufferView": 1,
+            "byteOffset": index_start,
+            "componentType": 5125,
+            "count": mesh.triangles.len() * 3,
+            "type": "SCALAR",
+        }));
+        gltf_meshes.push(json!({
+            "name": mesh.name,
+            "primitives": [{
+                "attributes": { "POSITION": accessors.len() - 2 },
+                "indices": accessors.len() - 1,
+                "material": usize::from(mesh.excluded),
+            }],
+        }));
+        position_start += mesh.vertices.len() * 3 * 4;
+        index_start += mesh.triangles.len() * 3 * 4;
+        nodes.push(json!({
+            "name": mesh.name,
+            "mesh": index,
+        }));
+    }
+
+    // glTF is y-up. The root node rotates the z-up point frame by
+    // -90 degrees around the x axis.
+    let children: Vec<_> = (0..meshes.len()).collect();
+    nodes.push(json!({
+        "name": "filter",
+        "rotation": [-std::f64::consts::FRAC_1_SQRT_2, 0.0, 0.0, std::f64::consts::FRAC_1_SQRT_2],
+        "children": children,
+    }));
+
+    let materials: Vec<_> = [false, true]
+        .into_iter()
+        .map(|excluded| {
+            json!({
+                "name": material_name(excluded),
+                "pbrMetallicRoughness": {
+                    "baseColorFactor": material_color(excluded),
+                    "metallicFactor": 0.0,
+                },
+                "alphaMode": "BLEND",
+                "doubleSided": true,
+            })
+        })
+        .collect();
+
+    let gltf = json!({
+        "asset": { "version": "2.0", "generator": "range-point-filter" },
+        "scene": 0,
+        "scenes": [{ "nodes": [meshes.len()] }],
+        "nodes": nodes,
+        "meshes": gltf_meshes,
+        "materials": materials,
+        "accessors": accessors,
+        "bufferViews": buffer_views,
+        "buffers": [{
+            "byteLength": buffer.len(),
+            "uri": format!("data:application/octet-stream;base64,{}", STANDARD.encode(&buffer)),
+        }],
+    });
+
+    fs::write(path, serde_json::to_string_pretty(&gltf)?)?;
+    Ok(())
+}
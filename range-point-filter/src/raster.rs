@@ -0,0 +1,308 @@
+use crate::{
+    item::{Item, Overlap},
+    FilterProgram,
+};
+use anyhow::{ensure, Context};
+use filter_expr::normal_form::Term;
+use nalgebra_0_32::Point3;
+use serde::{Deserialize, Serialize};
+use std::ops::RangeInclusive;
+
+/// A precompiled lookup table of a [FilterProgram] over a planar
+/// grid.
+///
+/// Each grid cell is either known to be accepted, known to be
+/// rejected, or lies on a boundary. Queries in known cells are
+/// answered by a bitmap lookup, while queries in boundary cells and
+/// outside the grid fall back to the exact program evaluation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "SerializedRasterProgram", into = "SerializedRasterProgram")]
+pub struct RasterProgram {
+    program: FilterProgram,
+    origin: [f64; 2],
+    resolution: f64,
+    shape: [usize; 2],
+    known: Vec<u32>,
+    accept: Vec<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SerializedRasterProgram {
+    program: FilterProgram,
+    origin: [f64; 2],
+    resolution: f64,
+    shape: [usize; 2],
+    known: Vec<u32>,
+    accept: Vec<u32>,
+}
+
+impl TryFrom<SerializedRasterProgram> for RasterProgram {
+    type Error = anyhow::Error;
+
+    fn try_from(from: SerializedRasterProgram) -> Result<Self, Self::Error> {
+        let SerializedRasterProgram {
+            program,
+            origin,
+            resolution,
+            shape,
+            known,
+            accept,
+        } = from;
+
+        ensure!(
+            origin.iter().all(|value| value.is_finite()),
+            "the origin {origin:?} is not finite"
+        );
+        ensure!(
+            resolution.is_finite() && resolution > 0.0,
+            "resolution must be a positive number, but get {resolution}"
+        );
+        let num_words = num_words(shape)?;
+        for (name, words) in [("known", &known), ("accept", &accept)] {
+            ensure!(
+                words.len() == num_words,
+                "the {name} bitmap has {} words, but expect {num_words} words for the shape {shape:?}",
+                words.len()
+            );
+        }
+
+        Ok(Self {
+            program,
+            origin,
+            resolution,
+            shape,
+            known,
+            accept,
+        })
+    }
+}
+
+impl From<RasterProgram> for SerializedRasterProgram {
+    fn from(from: RasterProgram) -> Self {
+        let RasterProgram {
+            program,
+            origin,
+            resolution,
+            shape,
+            known,
+            accept,
+        } = from;
+
+        Self {
+            program,
+            origin,
+            resolution,
+            shape,
+            known,
+            accept,
+        }
+    }
+}
+
+impl RasterProgram {
+    pub fn contains(&self, point: &Point3<f64>, intensity: Option<f64>) -> bool {
+        if let Some(index) = self.cell_index(point.x, point.y) {
+            if get_bit(&self.known, index) {
+                return get_bit(&self.accept, index);
+            }
+        }
+
+        self.program.contains(point, intensity)
+    }
+
+    pub fn program(&self) -> &FilterProgram {
+        &self.program
+    }
+
+    pub fn resolution(&self) -> f64 {
+        self.resolution
+    }
+
+    /// The number of cells along the x and y axes.
+    pub fn shape(&self) -> [usize; 2] {
+        self.shape
+    }
+
+    /// The number of cells that require exact evaluation.
+    pub fn num_boundary_cells(&self) -> usize {
+        let [nx, ny] = self.shape;
+        let num_known: usize = self.known.iter().map(|w| w.count_ones() as usize).sum();
+        nx * ny - num_known
+    }
+
+    fn cell_index(&self, x: f64, y: f64) -> Option<usize> {
+        let [ox, oy] = self.origin;
+        let [nx, ny] = self.shape;
+
+        let fx = ((x - ox) / self.resolution).floor();
+        let fy = ((y - oy) / self.resolution).floor();
+
+        // NaN coordinates are rejected by the range checks.
+        if !((0.0..nx as f64).contains(&fx) && (0.0..ny as f64).contains(&fy)) {
+            return None;
+        }
+
+        Some(fy as usize * nx + fx as usize)
+    }
+}
+
+impl FilterProgram {
+    /// Compiles the program into a planar lookup table covering
+    /// `bounds` with square cells of side length `resolution`.
+    pub fn rasterize(
+        &self,
+        resolution: f64,
+        bounds: [RangeInclusive<f64>; 2],
+    ) -> anyhow::Result<RasterProgram> {
+        ensure!(
+            resolution.is_finite() && resolution > 0.0,
+            "resolution must be a positive number, but get {resolution}"
+        );
+
+        let [x_bound, y_bound] = bounds;
+        for bound in [&x_bound, &y_bound] {
+            ensure!(
+                bound.start().is_finite() && bound.end().is_finite() && bound.start() < bound.end(),
+                "[{}, {}] is not a valid bound",
+                bound.start(),
+                bound.end()
+            );
+        }
+
+        let num_cells = |range: &RangeInclusive<f64>| {
+            let num_cells = ((range.end() - range.start()) / resolution).ceil();
+            ensure!(
+                num_cells < usize::MAX as f64,
+                "the resolution {resolution} is too fine for the bound [{}, {}]",
+                range.start(),
+                range.end()
+            );
+            Ok(num_cells as usize)
+        };
+        let nx = num_cells(&x_bound)?;
+        let ny = num_cells(&y_bound)?;
+        let num_words = num_words([nx, ny])?;
+
+        let mut known = vec![0u32; num_words];
+        let mut accept = vec![0u32; num_words];
+
+        for yi in 0..ny {
+            let y0 = y_bound.start() + yi as f64 * resolution;
+            let y_range = y0..=(y0 + resolution);
+
+            for xi in 0..nx {
+                let x0 = x_bound.start() + xi as f64 * resolution;
+                let x_range = x0..=(x0 + resolution);
+
+                let index = yi * nx + xi;
+                match self.classify_cell(&x_range, &y_range) {
+                    Truth::Yes => {
+                        set_bit(&mut known, index);
+                        set_bit(&mut accept, index);
+                    }
+                    Truth::No => set_bit(&mut known, index),
+                    Truth::Maybe => {}
+                }
+            }
+        }
+
+        Ok(RasterProgram {
+            program: self.clone(),
+            origin: [*x_bound.start(), *y_bound.start()],
+            resolution,
+            shape: [nx, ny],
+            known,
+            accept,
+        })
+    }
+
+    fn classify_cell(&self, x_range: &RangeInclusive<f64>, y_range: &RangeInclusive<f64>) -> Truth {
+        self.dnf
+            .0
+            .iter()
+            .map(|product| {
+                product
+                    .0
+                    .iter()
+                    .map(|term| {
+                        let Term { ref ident, invert } = *term;
+                        let truth = match self.items.get(&ident.0) {
+                            Item::Box(filter) => match filter.overlap_2d(x_range, y_range) {
+                                Overlap::Inside if filter.is_planar() => Truth::Yes,
+                                Overlap::Outside => Truth::No,
+                                _ => Truth::Maybe,
+                            },
+                            Item::Polygon(filter) => match filter.overlap_2d(x_range, y_range) {
+                                Overlap::Outside => Truth::No,
+                                _ => Truth::Maybe,
+                            },
+                            Item::Corridor(filter) => match filter.overlap_2d(x_range, y_range) {
+                                Overlap::Outside => Truth::No,
+                                _ => Truth::Maybe,
+                            },
+                            Item::Intensity(_) => Truth::Maybe,
+                        };
+
+                        if invert {
+                            truth.not()
+                        } else {
+                            truth
+                        }
+                    })
+                    .fold(Truth::Yes, Truth::and)
+            })
+            .fold(Truth::No, Truth::or)
+    }
+}
+
+/// Three-valued logic used to evaluate a program over a cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Truth {
+    Yes,
+    No,
+    Maybe,
+}
+
+impl Truth {
+    fn not(self) -> Self {
+        match self {
+            Truth::Yes => Truth::No,
+            Truth::No => Truth::Yes,
+            Truth::Maybe => Truth::Maybe,
+        }
+    }
+
+    fn and(self, other: Self) -> Self {
+        match (self, other) {
+            (Truth::No, _) | (_, Truth::No) => Truth::No,
+            (Truth::Yes, Truth::Yes) => Truth::Yes,
+            _ => Truth::Maybe,
+        }
+    }
+
+    fn or(self, other: Self) -> Self {
+        match (self, other) {
+            (Truth::Yes, _) | (_, Truth::Yes) => Truth::Yes,
+            (Truth::No, Truth::No) => Truth::No,
+            _ => Truth::Maybe,
+        }
+    }
+}
+
+/// The number of bitmap words for the grid shape.
+fn num_words([nx, ny]: [usize; 2]) -> anyhow::Result<usize> {
+    let num_cells = nx
+        .checked_mul(ny)
+        .with_context(|| format!("the grid of {nx}x{ny} cells is too large"))?;
+    Ok(num_cells.div_ceil(32))
+}
+
+// The bitmaps use 32-bit words, which are represented exactly by
+// number types of text formats such as JSON.
+fn get_bit(words: &[u32], index: usize) -> bool {
+    words[index / 32] & (1 << (index % 32)) != 0
+}
+
+fn set_bit(words: &mut [u32], index: usize) {
+    words[index / 32] |= 1 << (index % 32);
+}
@@ -0,0 +1,115 @@
+use crate::units;
+use anyhow::{ensure, Result};
+use itertools::Itertools;
+use nalgebra_0_32::{Isometry3, Translation3, UnitQuaternion};
+use noisy_float::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Enables an item only within time windows.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "with-schemars", derive(schemars::JsonSchema))]
+pub struct Schedule {
+    /// The period in seconds after which the windows repeat, for
+    /// example 86400 for a daily schedule. The windows are absolute
+    /// timestamps if not specified.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "with-schemars", schemars(with = "Option<f64>"))]
+    pub period: Option<R64>,
+
+    /// The offset in seconds added to timestamps before they are
+    /// matched against the windows, such as the time zone offset.
+    #[serde(default)]
+    #[cfg_attr(feature = "with-schemars", schemars(with = "f64"))]
+    pub offset: R64,
+
+    /// The `[start, end)` time windows in seconds when the item is
+    /// enabled.
+    #[cfg_attr(feature = "with-schemars", schemars(with = "Vec<[f64; 2]>"))]
+    pub windows: Vec<[R64; 2]>,
+}
+
+impl Schedule {
+    pub fn is_enabled(&self, time: f64) -> bool {
+        let mut time = time + self.offset.raw();
+        if let Some(period) = self.period {
+            time = time.rem_euclid(period.raw());
+        }
+
+        self.windows
+            .iter()
+            .any(|[start, end]| (start.raw()..end.raw()).contains(&time))
+    }
+
+    pub(crate) fn check(&self) -> Result<()> {
+        if let Some(period) = self.period {
+            ensure!(period > 0.0, "the period must be positive");
+        }
+
+        for [start, end] in &self.windows {
+            ensure!(start <= end, "[{start}, {end}) is not a valid time window");
+        }
+
+        Ok(())
+    }
+}
+
+/// The pose of a moving item at a timestamp.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "with-schemars", derive(schemars::JsonSchema))]
+pub struct Keyframe {
+    /// The timestamp in seconds.
+    #[cfg_attr(feature = "with-schemars", schemars(with = "f64"))]
+    pub t: R64,
+
+    /// The translation relative to the item definition.
+    #[serde(deserialize_with = "units::length")]
+    #[cfg_attr(feature = "with-schemars", schemars(with = "[units::Meters; 3]"))]
+    pub xyz: [R64; 3],
+
+    /// The rotation around the z axis relative to the item
+    /// definition.
+    #[serde(default, deserialize_with = "units::angle")]
+    #[cfg_attr(feature = "with-schemars", schemars(with = "units::Degrees"))]
+    pub azimuth_degrees: R64,
+}
+
+impl Keyframe {
+    pub fn pose(&self) -> Isometry3<f64> {
+        let [x, y, z] = self.xyz.map(|v| v.raw());
+        let yaw = self.azimuth_degrees.raw().to_radians();
+
+        let trans = Translation3::new(x, y, z);
+        let rot = UnitQuaternion::from_euler_angles(0.0, 0.0, yaw);
+        Isometry3::from_parts(trans, rot)
+    }
+}
+
+/// Interpolates the pose at a timestamp from the keyframes, which are
+/// sorted by timestamps. The pose stays at the first or the last
+/// keyframe out of the time span.
+pub(crate) fn interpolate(keyframes: &[Keyframe], time: f64) -> Option<Isometry3<f64>> {
+    let index = keyframes.partition_point(|frame| frame.t.raw() <= time);
+
+    let pose = match (index.checked_sub(1), keyframes.get(index)) {
+        (None, None) => return None,
+        (Some(prev), None) => keyframes[prev].pose(),
+        (None, Some(next)) => next.pose(),
+        (Some(prev), Some(next)) => {
+            let prev = &keyframes[prev];
+            let ratio = (time - prev.t.raw()) / (next.t - prev.t).raw();
+            prev.pose().lerp_slerp(&next.pose(), ratio)
+        }
+    };
+
+    Some(pose)
+}
+
+pub(crate) fn check_keyframes(keyframes: &[Keyframe]) -> Result<()> {
+    for (prev, next) in keyframes.iter().tuple_windows() {
+        ensure!(
+            prev.t < next.t,
+            "keyframes must be sorted by strictly increasing timestamps"
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,310 @@
+//! Config values annotated with units.
+//!
+//! Length and angle fields accept either bare numbers in the base
+//! unit of the field, or strings with unit suffixes such as
+//! `"1200mm"`, `"0.5rad"` and `"30deg"`. Values are converted to the
+//! base unit when the config is loaded, and serialized back as bare
+//! numbers.
+//!
+//! ```ignore
+//! #[derive(Deserialize)]
+//! struct Config {
+//!     #[serde(deserialize_with = "units::length")]
+//!     width: R64,
+//! }
+//! ```
+
+use crate::utils::SerializedBound;
+use noisy_float::prelude::*;
+use serde::{de::DeserializeOwned, de::Error as _, Deserialize, Deserializer};
+use std::{marker::PhantomData, ops::Bound};
+
+/// A kind of physical quantity.
+pub trait Unit {
+    /// The name of the quantity in error messages.
+    const NAME: &'static str;
+
+    /// The name of the base unit.
+    const BASE: &'static str;
+
+    /// The unit suffixes and their scales to the base unit.
+    const SUFFIXES: &'static [(&'static str, f64)];
+}
+
+/// Lengths in meters.
+#[derive(Debug, Clone, Copy)]
+pub enum Length {}
+
+impl Unit for Length {
+    const NAME: &'static str = "length";
+    const BASE: &'static str = "meters";
+    const SUFFIXES: &'static [(&'static str, f64)] =
+        &[("m", 1.0), ("cm", 1e-2), ("mm", 1e-3), ("km", 1e3)];
+}
+
+/// Angles in degrees.
+#[derive(Debug, Clone, Copy)]
+pub enum Angle {}
+
+impl Unit for Angle {
+    const NAME: &'static str = "angle";
+    const BASE: &'static str = "degrees";
+    const SUFFIXES: &'static [(&'static str, f64)] =
+        &[("deg", 1.0), ("rad", 180.0 / std::f64::consts::PI)];
+}
+
+/// A length in meters.
+pub type Meters = Quantity<Length>;
+
+/// An angle in degrees.
+pub type Degrees = Quantity<Angle>;
+
+/// A value in the base unit, which is deserialized from a number or
+/// a string with a unit suffix.
+#[derive(Debug, Clone, Copy)]
+pub struct Quantity<U: Unit> {
+    pub value: f64,
+    _unit: PhantomData<U>,
+}
+
+impl<U: Unit> Quantity<U> {
+    /// Parses a number with an optional unit suffix.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let text = text.trim();
+        let number = text.trim_end_matches(|c: char| c.is_alphabetic());
+        let suffix = &text[number.len()..];
+
+        let scale = if suffix.is_empty() {
+            1.0
+        } else {
+            let Some(&(_, scale)) = U::SUFFIXES.iter().find(|(unit, _)| *unit == suffix) else {
+                let units: Vec<_> = U::SUFFIXES.iter().map(|(unit, _)| *unit).collect();
+                return Err(format!(
+                    r#"unknown {} unit "{suffix}" in "{text}", expect one of {}"#,
+                    U::NAME,
+                    units.join(", ")
+                ));
+            };
+            scale
+        };
+
+        let number: f64 = number
+            .trim()
+            .parse()
+            .map_err(|_| format!(r#"invalid {} "{text}""#, U::NAME))?;
+        Self::new(number * scale)
+    }
+
+    fn new(value: f64) -> Result<Self, String> {
+        if !value.is_finite() {
+            return Err(format!("the {} {value} is not finite", U::NAME));
+        }
+        Ok(Self {
+            value,
+            _unit: PhantomData,
+        })
+    }
+
+    /// Converts the value to `f32`, which fails if it is out of range.
+    fn as_f32(&self) -> Result<f32, String> {
+        let value = self.value as f32;
+        if !value.is_finite() {
+            return Err(format!("the {} {} overflows f32", U::NAME, self.value));
+        }
+        Ok(value)
+    }
+}
+
+impl<'de, U: Unit> Deserialize<'de> for Quantity<U> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Number(f64),
+            Text(String),
+        }
+
+        let quantity = match Raw::deserialize(deserializer).map_err(|_| {
+            D::Error::custom(format!(
+                "expect a {} in a number or a string with a unit",
+                U::NAME
+            ))
+        })? {
+            Raw::Number(value) => Self::new(value),
+            Raw::Text(text) => Self::parse(&text),
+        };
+        quantity.map_err(D::Error::custom)
+    }
+}
+
+#[cfg(feature = "with-schemars")]
+impl<U: Unit> schemars::JsonSchema for Quantity<U> {
+    fn schema_name() -> String {
+        let mut name = U::NAME.to_string();
+        name[..1].make_ascii_uppercase();
+        name
+    }
+
+    fn json_schema(_: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        use schemars::schema::{
+            InstanceType, Metadata, SchemaObject, StringValidation, SubschemaValidation,
+        };
+
+        let units: Vec<_> = U::SUFFIXES.iter().map(|(unit, _)| *unit).collect();
+        let number = SchemaObject {
+            instance_type: Some(InstanceType::Number.into()),
+            ..Default::default()
+        };
+        let text = SchemaObject {
+            instance_type: Some(InstanceType::String.into()),
+            string: Some(Box::new(StringValidation {
+                pattern: Some(format!(
+                    r"^\s*[-+]?(\d+\.?\d*|\.\d+)([eE][-+]?\d+)?\s*({})?\s*$",
+                    units.join("|")
+                )),
+                ..Default::default()
+            })),
+            ..Default::default()
+        };
+
+        SchemaObject {
+            metadata: Some(Box::new(Metadata {
+                description: Some(format!(
+                    "A {} in {}, or a string with one of the units {}.",
+                    U::NAME,
+                    U::BASE,
+                    units.join(", ")
+                )),
+                ..Default::default()
+            })),
+            subschemas: Some(Box::new(SubschemaValidation {
+                any_of: Some(vec![number.into(), text.into()]),
+                ..Default::default()
+            })),
+            ..Default::default()
+        }
+        .into()
+    }
+}
+
+/// Values which can be loaded from quantities in the same shape.
+pub trait FromQuantity<U: Unit>: Sized {
+    type Raw: DeserializeOwned;
+
+    fn from_quantity(raw: Self::Raw) -> Result<Self, String>;
+}
+
+impl<U: Unit> FromQuantity<U> for f64 {
+    type Raw = Quantity<U>;
+
+    fn from_quantity(raw: Self::Raw) -> Result<Self, String> {
+        Ok(raw.value)
+    }
+}
+
+impl<U: Unit> FromQuantity<U> for R64 {
+    type Raw = Quantity<U>;
+
+    fn from_quantity(raw: Self::Raw) -> Result<Self, String> {
+        Ok(r64(raw.value))
+    }
+}
+
+impl<U: Unit> FromQuantity<U> for R32 {
+    type Raw = Quantity<U>;
+
+    fn from_quantity(raw: Self::Raw) -> Result<Self, String> {
+        raw.as_f32().map(r32)
+    }
+}
+
+impl<U: Unit> FromQuantity<U> for N32 {
+    type Raw = Quantity<U>;
+
+    fn from_quantity(raw: Self::Raw) -> Result<Self, String> {
+        raw.as_f32().map(n32)
+    }
+}
+
+impl<U: Unit, T: FromQuantity<U>> FromQuantity<U> for Option<T> {
+    type Raw = Option<T::Raw>;
+
+    fn from_quantity(raw: Self::Raw) -> Result<Self, String> {
+        raw.map(T::from_quantity).transpose()
+    }
+}
+
+impl<U: Unit, T: FromQuantity<U>> FromQuantity<U> for Vec<T> {
+    type Raw = Vec<T::Raw>;
+
+    fn from_quantity(raw: Self::Raw) -> Result<Self, String> {
+        raw.into_iter().map(T::from_quantity).collect()
+    }
+}
+
+macro_rules! impl_array {
+    ($($len:literal),*) => {
+        $(
+            impl<U: Unit, T: FromQuantity<U>> FromQuantity<U> for [T; $len] {
+                type Raw = [T::Raw; $len];
+
+                fn from_quantity(raw: Self::Raw) -> Result<Self, String> {
+                    let values: Vec<T> = raw
+                        .into_iter()
+                        .map(T::from_quantity)
+                        .collect::<Result<_, _>>()?;
+                    Ok(values.try_into().unwrap_or_else(|_| unreachable!()))
+                }
+            }
+        )*
+    };
+}
+
+impl_array!(2, 3);
+
+impl<U: Unit, T: FromQuantity<U>> FromQuantity<U> for (Bound<T>, Bound<T>) {
+    type Raw = SerializedBound<T::Raw>;
+
+    fn from_quantity(raw: Self::Raw) -> Result<Self, String> {
+        let (lower, upper) = raw.into_bound()?;
+        let convert = |bound: Bound<T::Raw>| -> Result<Bound<T>, String> {
+            Ok(match bound {
+                Bound::Included(value) => Bound::Included(T::from_quantity(value)?),
+                Bound::Excluded(value) => Bound::Excluded(T::from_quantity(value)?),
+                Bound::Unbounded => Bound::Unbounded,
+            })
+        };
+        Ok((convert(lower)?, convert(upper)?))
+    }
+}
+
+/// Deserializes lengths in meters.
+pub fn length<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromQuantity<Length>,
+{
+    deserialize_quantity(deserializer)
+}
+
+/// Deserializes angles in degrees.
+pub fn angle<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromQuantity<Angle>,
+{
+    deserialize_quantity(deserializer)
+}
+
+fn deserialize_quantity<'de, D, U, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    U: Unit,
+    T: FromQuantity<U>,
+{
+    let raw = T::Raw::deserialize(deserializer)?;
+    T::from_quantity(raw).map_err(D::Error::custom)
+}
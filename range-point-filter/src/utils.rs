@@ -1,5 +1,7 @@
+use anyhow::{bail, Result};
+use noisy_float::prelude::*;
 use serde::{Deserialize, Serialize};
-use std::ops::{Bound, Bound::*};
+use std::ops::{Bound, Bound::*, RangeBounds};
 
 fn unpack<T>(bound: &Bound<T>) -> (Option<&T>, Option<&T>) {
     match bound {
@@ -20,14 +22,16 @@ fn pack<T>(bound: Option<T>, ibound: Option<T>) -> Option<Bound<T>> {
 }
 
 #[derive(Serialize, Deserialize)]
-struct SerializedBound<T> {
-    #[serde(rename = ">")]
+#[cfg_attr(feature = "with-schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "with-schemars", schemars(rename = "Bound_of_{T}"))]
+pub struct SerializedBound<T> {
+    #[serde(rename = ">", skip_serializing_if = "Option::is_none")]
     pub min: Option<T>,
-    #[serde(rename = ">=")]
+    #[serde(rename = ">=", skip_serializing_if = "Option::is_none")]
     pub imin: Option<T>,
-    #[serde(rename = "<")]
+    #[serde(rename = "<", skip_serializing_if = "Option::is_none")]
     pub max: Option<T>,
-    #[serde(rename = "<=")]
+    #[serde(rename = "<=", skip_serializing_if = "Option::is_none")]
     pub imax: Option<T>,
 }
 
@@ -54,8 +58,8 @@ impl<T> SerializedBound<T> {
             imax,
         } = self;
 
-        let lower = pack(min, imin).ok_or("min and imin must not be both specified")?;
-        let upper = pack(max, imax).ok_or("max and imax must not be both specified")?;
+        let lower = pack(min, imin).ok_or("\">\" and \">=\" must not be both specified")?;
+        let upper = pack(max, imax).ok_or("\"<\" and \"<=\" must not be both specified")?;
 
         Ok((lower, upper))
     }
@@ -83,3 +87,130 @@ pub mod serde_bound {
         raw.into_bound().map_err(D::Error::custom)
     }
 }
+
+pub fn unbounded<T>() -> (Bound<T>, Bound<T>) {
+    (Unbounded, Unbounded)
+}
+
+pub fn is_unbounded<T>((lower, upper): &(Bound<T>, Bound<T>)) -> bool {
+    matches!((lower, upper), (Unbounded, Unbounded))
+}
+
+/// An interval whose ends are inclusive, exclusive or unbounded.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Interval {
+    pub lower: Bound<f64>,
+    pub upper: Bound<f64>,
+}
+
+impl Interval {
+    /// Builds the interval from the bound syntax and the legacy
+    /// limits, which must not be both specified on the same side.
+    pub fn from_config(
+        bound: &(Bound<R64>, Bound<R64>),
+        legacy_lower: Option<Bound<R64>>,
+        legacy_upper: Option<Bound<R64>>,
+    ) -> Result<Self> {
+        let (lower, upper) = bound;
+        let merge = |bound: &Bound<R64>, legacy: Option<Bound<R64>>| -> Result<Bound<f64>> {
+            let bound = match (bound, legacy) {
+                (bound, None) => *bound,
+                (Unbounded, Some(legacy)) => legacy,
+                (_, Some(_)) => bail!("the legacy limit and the bound syntax are both specified"),
+            };
+            Ok(bound.map(|v| v.raw()))
+        };
+
+        Ok(Self {
+            lower: merge(lower, legacy_lower)?,
+            upper: merge(upper, legacy_upper)?,
+        })
+    }
+
+    pub fn to_config(self) -> (Bound<R64>, Bound<R64>) {
+        (self.lower.map(r64), self.upper.map(r64))
+    }
+
+    /// The lower limit, or negative infinity if it is unbounded.
+    pub fn start(&self) -> f64 {
+        match self.lower {
+            Included(v) | Excluded(v) => v,
+            Unbounded => f64::NEG_INFINITY,
+        }
+    }
+
+    /// The upper limit, or positive infinity if it is unbounded.
+    pub fn end(&self) -> f64 {
+        match self.upper {
+            Included(v) | Excluded(v) => v,
+            Unbounded => f64::INFINITY,
+        }
+    }
+
+    /// Checks if the value is in the interval. NaN is never
+    /// contained.
+    pub fn contains(&self, value: f64) -> bool {
+        !value.is_nan() && (self.lower, self.upper).contains(&value)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        let (start, end) = (self.start(), self.end());
+        start > end
+            || (start == end && !matches!((self.lower, self.upper), (Included(_), Included(_))))
+    }
+
+    pub fn is_unbounded(&self) -> bool {
+        matches!((self.lower, self.upper), (Unbounded, Unbounded))
+    }
+
+    pub fn is_bounded(&self) -> bool {
+        !matches!(self.lower, Unbounded) && !matches!(self.upper, Unbounded)
+    }
+
+    /// Moves the interval by the offset.
+    pub fn shift(&self, offset: f64) -> Self {
+        Self {
+            lower: self.lower.map(|v| v + offset),
+            upper: self.upper.map(|v| v + offset),
+        }
+    }
+}
+
+impl std::fmt::Display for Interval {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.lower {
+            Included(v) => write!(f, "[{v}, ")?,
+            Excluded(v) => write!(f, "({v}, ")?,
+            Unbounded => write!(f, "(-inf, ")?,
+        }
+        match self.upper {
+            Included(v) => write!(f, "{v}]"),
+            Excluded(v) => write!(f, "{v})"),
+            Unbounded => write!(f, "inf)"),
+        }
+    }
+}
+
+/// Implements `JsonSchema` for a type serialized through another
+/// type.
+#[cfg(feature = "with-schemars")]
+macro_rules! impl_json_schema_as {
+    ($ty:ty => $as:ty) => {
+        impl schemars::JsonSchema for $ty {
+            fn schema_name() -> String {
+                <$as as schemars::JsonSchema>::schema_name()
+            }
+
+            fn json_schema(gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+                <$as as schemars::JsonSchema>::json_schema(gen)
+            }
+
+            fn is_referenceable() -> bool {
+                <$as as schemars::JsonSchema>::is_referenceable()
+            }
+        }
+    };
+}
+
+#[cfg(feature = "with-schemars")]
+pub(crate) use impl_json_schema_as;
@@ -7,6 +7,7 @@ use kiss3d::window::Window;
 use kiss3d_utils::WindowPlotExt as _;
 use nalgebra_0_30 as na30;
 use nalgebra_0_32 as na32;
+use nalgebra_0_32::Point2;
 
 impl FilterProgram {
     pub fn render_kiss3d(&self, window: &mut Window) {
@@ -18,9 +19,19 @@ impl FilterProgram {
                 let neg_color = na30::Point3::new(1.0, 0.0, 0.0);
                 let color = if invert { neg_color } else { pos_color };
 
-                let item = &self.items[&ident.0];
+                let item = self.items.get(&ident.0);
                 match item {
                     Item::Box(filter) => filter.render_kiss3d(window, &color),
+                    Item::Polygon(filter) => {
+                        let z = filter.z_range.start().max(0.0);
+                        for ring in std::iter::once(&filter.vertices).chain(&filter.holes) {
+                            draw_polyline(window, ring, true, z, &color);
+                        }
+                    }
+                    Item::Corridor(filter) => {
+                        let z = filter.z_range.start().max(0.0);
+                        draw_polyline(window, &filter.path, false, z, &color);
+                    }
                     Item::Intensity(_) => {}
                 }
             }
@@ -59,3 +70,25 @@ impl PlanarBoxFilter {
         }
     }
 }
+
+/// Draws the line through the points at the height.
+fn draw_polyline(
+    window: &mut Window,
+    points: &[Point2<f64>],
+    closed: bool,
+    z: f64,
+    color: &na30::Point3<f32>,
+) {
+    let to_point = |p: &Point2<f64>| na30::Point3::new(p.x as f32, p.y as f32, z as f32);
+
+    let num_lines = if closed {
+        points.len()
+    } else {
+        points.len().saturating_sub(1)
+    };
+    for index in 0..num_lines {
+        let a = &points[index];
+        let b = &points[(index + 1) % points.len()];
+        window.draw_line(&to_point(a), &to_point(b), color);
+    }
+}
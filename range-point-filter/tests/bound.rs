@@ -0,0 +1,77 @@
+use anyhow::Result;
+use nalgebra_0_32::Point3;
+use range_point_filter::FilterProgram;
+use std::fs;
+
+#[test]
+fn bound_syntax_test() -> Result<()> {
+    let config = fs::read_to_string("tests/bound.json5")?;
+    let program: FilterProgram = json5::from_str(&config)?;
+
+    let samples = [
+        // Edges of the z bounds
+        (Point3::new(1.0, 2.0, 1.0), Some(1.0), false),
+        (Point3::new(1.0, 2.0, 4.0), Some(1.0), true),
+        (Point3::new(3.0, 2.0, 2.0), Some(1.0), false),
+        (Point3::new(3.0, 2.0, 3.0), Some(1.0), true),
+        // Edges of the intensity bound
+        (Point3::new(1.0, 2.0, 2.0), Some(0.5), true),
+        (Point3::new(1.0, 2.0, 2.0), Some(2.5), true),
+        (Point3::new(1.0, 2.0, 2.0), Some(2.6), false),
+        (Point3::new(1.0, 2.0, 2.0), Some(f64::NAN), false),
+    ];
+
+    for (point, intensity, expect) in samples {
+        assert_eq!(
+            program.contains(&point, intensity),
+            expect,
+            "point={point} intensity={intensity:?}"
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn legacy_limits_test() -> Result<()> {
+    let config = fs::read_to_string("tests/box_filter.json5")?;
+    let program: FilterProgram = json5::from_str(&config)?;
+
+    // The legacy intensity max is exclusive.
+    let point = Point3::new(1.0, 2.0, 2.0);
+    assert!(program.contains(&point, Some(0.5)));
+    assert!(!program.contains(&point, Some(2.5)));
+
+    // Legacy limits survive the round trip.
+    let text = serde_json::to_string(&program)?;
+    let reloaded: FilterProgram = serde_json::from_str(&text)?;
+    assert!(reloaded.contains(&point, Some(0.5)));
+    assert!(!reloaded.contains(&point, Some(2.5)));
+
+    Ok(())
+}
+
+#[test]
+fn conflicting_limits_test() {
+    let config = r#"
+{
+    "program": "box",
+    "items": {
+        "box": {
+            "type": "box",
+            "xy": [0, 0],
+            "wh": [1, 1],
+            "z_min": 0,
+            "z": { ">": 1 },
+            "azimuth_degrees": 0
+        }
+    }
+}
+"#;
+    assert!(json5::from_str::<FilterProgram>(config).is_err());
+
+    let config = config
+        .replace(r#""z_min": 0,"#, "")
+        .replace(r#""z": { ">": 1 }"#, r#""z": { ">": 1, ">=": 1 }"#);
+    assert!(json5::from_str::<FilterProgram>(&config).is_err());
+}
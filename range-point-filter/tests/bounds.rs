@@ -0,0 +1,42 @@
+use anyhow::Result;
+use nalgebra_0_32::Point3;
+use range_point_filter::{Aabb, FilterProgram};
+use std::fs;
+
+#[test]
+fn bounds_test() -> Result<()> {
+    let config = fs::read_to_string("tests/box_filter.json5")?;
+    let program: FilterProgram = json5::from_str(&config)?;
+
+    let expect = Aabb::new(Point3::new(0.0, 0.0, 1.0), Point3::new(6.0, 4.0, 4.0));
+    assert_eq!(program.bounds(), Some(expect));
+    assert_eq!(program.bounds_over_time(), Some(expect));
+
+    Ok(())
+}
+
+#[test]
+fn unbounded_test() -> Result<()> {
+    let program: FilterProgram = json5::from_str(
+        r#"
+{
+    "program": "int + box",
+    "items": {
+        "box": {
+            "type": "box",
+            "xy": [0, 0],
+            "wh": [2, 2],
+            "azimuth_degrees": 45
+        },
+        "int": {
+            "type": "intensity",
+            "min": 0.5
+        }
+    }
+}
+"#,
+    )?;
+    assert_eq!(program.bounds(), None);
+
+    Ok(())
+}
@@ -0,0 +1,36 @@
+use anyhow::Result;
+use nalgebra_0_32::Point3;
+use range_point_filter::FilterProgram;
+use std::fs;
+
+#[test]
+fn explain_test() -> Result<()> {
+    let config = fs::read_to_string("tests/box_filter.json5")?;
+    let program: FilterProgram = json5::from_str(&config)?;
+
+    let samples = [
+        (Point3::new(3.0, 2.0, 2.5), Some(1.0)),
+        (Point3::new(3.0, 2.0, 1.0), Some(1.0)),
+        (Point3::new(3.0, 2.0, 1.0), Some(3.0)),
+    ];
+
+    for (point, intensity) in samples {
+        let explanation = program.explain(&point, intensity);
+        assert_eq!(
+            explanation.accepted(),
+            program.contains(&point, intensity),
+            "point={point} intensity={intensity:?}"
+        );
+    }
+
+    let explanation = program.explain(&Point3::new(3.0, 2.0, 2.5), Some(1.0));
+    let items: Vec<_> = explanation
+        .items
+        .iter()
+        .map(|(name, yes)| (name.as_str(), *yes))
+        .collect();
+    assert_eq!(items, [("box1", true), ("box2", true), ("int", true)]);
+    assert!(explanation.to_string().ends_with("result: rejected"));
+
+    Ok(())
+}
@@ -0,0 +1,47 @@
+use anyhow::Result;
+use nalgebra_0_32::Point3;
+use noisy_float::prelude::*;
+use range_point_filter::{frame::FrameConfig, FilterProgram};
+use std::fs;
+
+#[test]
+fn frame_test() -> Result<()> {
+    let config = fs::read_to_string("tests/frame.json5")?;
+    let mut program: FilterProgram = json5::from_str(&config)?;
+
+    // box1 is rotated by 90 degrees and lowered by 1 meter.
+    assert!(program.contains(&Point3::new(10.0, 1.9, 0.0), None));
+    assert!(!program.contains(&Point3::new(11.9, 0.0, 0.0), None));
+    assert!(!program.contains(&Point3::new(10.0, 1.9, 1.5), None));
+
+    // box2 is placed 1 meter along the rotated y axis of the site.
+    assert!(program.contains(&Point3::new(10.0, 0.0, 0.0), None));
+    assert!(!program.contains(&Point3::new(9.2, 0.0, 0.0), None));
+
+    // Moving the site frame relocates items attached to it and its
+    // children.
+    program.set_frame(
+        "site",
+        FrameConfig {
+            parent: None,
+            xyz: [r64(0.0), r64(0.0), r64(0.0)],
+            rpy_degrees: [r64(0.0); 3],
+        },
+    )?;
+    assert!(program.contains(&Point3::new(1.9, 0.0, 1.0), None));
+    assert!(!program.contains(&Point3::new(0.0, 0.8, 1.0), None));
+    assert!(!program.contains(&Point3::new(10.0, 1.9, 0.0), None));
+
+    // The program is serialized in the original frames.
+    let program: FilterProgram = json5::from_str(&json5::to_string(&program)?)?;
+    assert!(program.contains(&Point3::new(1.9, 0.0, 1.0), None));
+
+    // Frames must not form a cycle.
+    let text = config.replace(
+        r#""xyz": [10, 0, -1],"#,
+        r#""parent": "gate", "xyz": [10, 0, -1],"#,
+    );
+    assert!(json5::from_str::<FilterProgram>(&text).is_err());
+
+    Ok(())
+}
@@ -0,0 +1,116 @@
+use anyhow::Result;
+use nalgebra_0_32::Point3;
+use noisy_float::prelude::*;
+use range_point_filter::{
+    geodetic::{from_geojson, GeoJsonOptions, GeodeticOrigin},
+    item::Item,
+    FilterProgram,
+};
+use std::fs;
+
+#[test]
+fn geodetic_projection_test() {
+    let origin = GeodeticOrigin {
+        latitude: r64(25.0),
+        longitude: r64(121.5),
+        altitude: r64(0.0),
+        heading_degrees: r64(90.0),
+    };
+
+    // One arc-second of latitude is about 30.7 meters.
+    let p = origin.project(121.5, 25.0 + 1.0 / 3600.0, 0.0);
+    assert!(p.x.abs() < 1e-6 && (p.y - 30.7).abs() < 0.1, "p={p}");
+
+    // The x axis points to the north if the heading is zero.
+    let origin = GeodeticOrigin {
+        heading_degrees: r64(0.0),
+        ..origin
+    };
+    let p = origin.project(121.5, 25.0 + 1.0 / 3600.0, 0.0);
+    assert!((p.x - 30.7).abs() < 0.1 && p.y.abs() < 1e-6, "p={p}");
+}
+
+#[test]
+fn geojson_import_test() -> Result<()> {
+    let text = fs::read_to_string("tests/geodetic.geojson")?;
+    let options = GeoJsonOptions {
+        origin: GeodeticOrigin {
+            latitude: r64(25.0),
+            longitude: r64(121.5),
+            altitude: r64(0.0),
+            heading_degrees: r64(90.0),
+        },
+        name_property: "name".to_string(),
+        corridor_width: Some(r64(4.0)),
+        z_min: None,
+        z_max: None,
+    };
+    let items = from_geojson(&text, &options)?;
+
+    let mut names: Vec<_> = items.keys().map(|name| name.as_str()).collect();
+    names.sort_unstable();
+    assert_eq!(
+        names,
+        ["Crosswalk_A", "islands_0", "islands_1", "lane", "plaza"]
+    );
+    assert!(matches!(items["Crosswalk_A"].item, Item::Box(_)));
+    assert!(matches!(items["plaza"].item, Item::Polygon(_)));
+    assert!(matches!(items["lane"].item, Item::Corridor(_)));
+
+    let config = serde_json::json!({
+        "program": "Crosswalk_A + plaza + lane",
+        "items": items,
+    });
+    let program: FilterProgram = serde_json::from_value(config)?;
+
+    let origin = &options.origin;
+    let samples = [
+        (origin.project(121.5, 25.0, 0.0), true),
+        (origin.project(121.5012, 25.0002, 0.0), true),
+        (origin.project(121.5018, 25.0008, 0.0), false),
+        (origin.project(121.5, 25.0020, 0.0), true),
+        (origin.project(121.5, 25.0021, 0.0), false),
+    ];
+    for (point, expect) in samples {
+        let point = Point3::new(point.x, point.y, 0.0);
+        assert_eq!(program.contains(&point, None), expect, "point={point}");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn degenerate_rectangle_test() -> Result<()> {
+    // A segment traced back and forth has the 4 vertices of a
+    // rectangle, but two of its edges have zero length.
+    let text = r#"
+{
+    "type": "Feature",
+    "properties": { "name": "segment" },
+    "geometry": {
+        "type": "Polygon",
+        "coordinates": [[
+            [121.5000, 25.0000], [121.5000, 25.0000],
+            [121.5010, 25.0000], [121.5010, 25.0000],
+            [121.5000, 25.0000]
+        ]]
+    }
+}
+"#;
+    let options = GeoJsonOptions {
+        origin: GeodeticOrigin {
+            latitude: r64(25.0),
+            longitude: r64(121.5),
+            altitude: r64(0.0),
+            heading_degrees: r64(90.0),
+        },
+        name_property: "name".to_string(),
+        corridor_width: None,
+        z_min: None,
+        z_max: None,
+    };
+    let items = from_geojson(text, &options)?;
+    assert!(matches!(items["segment"].item, Item::Polygon(_)));
+
+    Ok(())
+}
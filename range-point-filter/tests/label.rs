@@ -0,0 +1,32 @@
+use anyhow::Result;
+use nalgebra_0_32::Point3;
+use range_point_filter::LabelProgram;
+use std::fs;
+
+#[test]
+fn label_test() -> Result<()> {
+    let config = fs::read_to_string("tests/label.json5")?;
+    let program: LabelProgram = json5::from_str(&config)?;
+
+    let labels: Vec<_> = program.labels().collect();
+    assert_eq!(labels, ["crosswalk", "lane_north", "lane_south"]);
+
+    let samples = [
+        (Point3::new(0.0, 2.0, 0.0), Some(1), 0b010),
+        (Point3::new(0.0, -2.0, 0.0), Some(2), 0b100),
+        (Point3::new(20.0, 2.0, 0.0), Some(0), 0b011),
+        (Point3::new(20.0, -2.0, 0.0), Some(0), 0b101),
+        (Point3::new(0.0, 20.0, 0.0), None, 0b000),
+    ];
+
+    for (point, label, mask) in samples {
+        assert_eq!(program.classify(&point, None), label, "point={point}");
+        assert_eq!(program.label_mask(&point, None), mask, "point={point}");
+    }
+
+    let labels = program.classify_batch(samples.iter().map(|(point, _, _)| (*point, None)));
+    let expect: Vec<_> = samples.iter().map(|(_, label, _)| *label).collect();
+    assert_eq!(labels, expect);
+
+    Ok(())
+}
@@ -0,0 +1,34 @@
+use anyhow::Result;
+use filter_expr::parser::parse_str;
+use nalgebra_0_32::Point3;
+use range_point_filter::lanelet2::{from_lanelet2, Lanelet2Options};
+use std::fs;
+
+#[test]
+fn lanelet2_import_test() -> Result<()> {
+    let text = fs::read_to_string("tests/lanelet2.osm")?;
+    let regions = from_lanelet2(&text, &Lanelet2Options::default())?;
+
+    assert_eq!(regions.lanelets, ["lanelet_1000"]);
+    assert_eq!(regions.crosswalks, ["crosswalk_1001"]);
+    assert_eq!(regions.areas, ["area_1002"]);
+
+    let program = regions.default_program()?;
+    assert_eq!(program, parse_str("lanelet_1000 + crosswalk_1001")?);
+    let program = regions.to_filter_program(program)?;
+
+    let samples = [
+        (Point3::new(5.0, 1.0, 0.0), true),
+        (Point3::new(21.0, 5.0, 0.0), true),
+        (Point3::new(5.0, 5.0, 0.0), false),
+        (Point3::new(5.0, 15.0, 0.0), false),
+    ];
+    for (point, expect) in samples {
+        assert_eq!(program.contains(&point, None), expect, "point={point}");
+    }
+
+    let program = regions.to_filter_program(parse_str("area_1002")?)?;
+    assert!(program.contains(&Point3::new(5.0, 15.0, 0.0), None));
+
+    Ok(())
+}
@@ -0,0 +1,39 @@
+use anyhow::Result;
+use range_point_filter::{
+    lint::{Location, Severity},
+    FilterProgram,
+};
+use std::fs;
+
+#[test]
+fn lint_test() -> Result<()> {
+    let config = fs::read_to_string("tests/lint.json5")?;
+    let program: FilterProgram = json5::from_str(&config)?;
+
+    let mut findings: Vec<_> = program
+        .lint()
+        .into_iter()
+        .map(|finding| {
+            let location = match finding.location {
+                Location::Item(name) => name,
+                Location::Product { .. } => "product".to_string(),
+            };
+            (location, finding.severity)
+        })
+        .collect();
+    findings.sort();
+
+    assert_eq!(
+        findings,
+        [
+            ("far".to_string(), Severity::Warning),
+            ("int".to_string(), Severity::Error),
+            ("product".to_string(), Severity::Warning),
+            ("right".to_string(), Severity::Warning),
+            ("unused".to_string(), Severity::Warning),
+            ("unused".to_string(), Severity::Error),
+        ]
+    );
+
+    Ok(())
+}
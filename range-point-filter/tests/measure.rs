@@ -0,0 +1,102 @@
+use anyhow::Result;
+use nalgebra_0_32::Point3;
+use range_point_filter::{Aabb, FilterProgram};
+use std::fs;
+
+#[test]
+fn exact_measure_test() -> Result<()> {
+    let program: FilterProgram = json5::from_str(
+        r#"
+{
+    "program": "box1 + box2",
+    "items": {
+        "box1": {
+            "type": "box",
+            "xy": [0, 0],
+            "wh": [4, 2],
+            "z_min": 0,
+            "z_max": 2,
+            "azimuth_degrees": 0
+        },
+        "box2": {
+            "type": "box",
+            "xy": [0, 0],
+            "wh": [4, 2],
+            "z_min": 0,
+            "z_max": 1,
+            "azimuth_degrees": 90
+        }
+    }
+}
+"#,
+    )?;
+
+    let bounds = Aabb::new(
+        Point3::new(-10.0, -10.0, -10.0),
+        Point3::new(10.0, 10.0, 10.0),
+    );
+    let volume = program.estimate_volume(&bounds, 0.01, 0)?;
+    assert!(volume.is_exact());
+    assert!((volume.value - (16.0 + 8.0 - 4.0)).abs() < 1e-9);
+
+    let area = program.estimate_area([-10.0..=10.0, -10.0..=10.0], 0.5, 0.01, 0)?;
+    assert!(area.is_exact());
+    assert!((area.value - 12.0).abs() < 1e-9);
+
+    let area = program.estimate_area([-10.0..=10.0, -10.0..=10.0], 1.5, 0.01, 0)?;
+    assert!((area.value - 8.0).abs() < 1e-9);
+
+    Ok(())
+}
+
+#[test]
+fn negative_extent_measure_test() -> Result<()> {
+    let program: FilterProgram = json5::from_str(
+        r#"
+{
+    "program": "box",
+    "items": {
+        "box": {
+            "type": "box",
+            "xy": [0, 0],
+            "wh": [-4, -2],
+            "z_min": 0,
+            "z_max": 2,
+            "azimuth_degrees": 0
+        }
+    }
+}
+"#,
+    )?;
+
+    let bounds = Aabb::new(
+        Point3::new(-10.0, -10.0, -10.0),
+        Point3::new(10.0, 10.0, 10.0),
+    );
+    let volume = program.estimate_volume(&bounds, 0.01, 0)?;
+    assert!(volume.is_exact());
+    assert_eq!(volume.value, 0.0);
+
+    let area = program.estimate_area([-10.0..=10.0, -10.0..=10.0], 1.0, 0.01, 0)?;
+    assert!(area.is_exact());
+    assert_eq!(area.value, 0.0);
+
+    Ok(())
+}
+
+#[test]
+fn monte_carlo_measure_test() -> Result<()> {
+    let config = fs::read_to_string("tests/box_filter.json5")?;
+    let program: FilterProgram = json5::from_str(&config)?;
+
+    let bounds = program.bounds().unwrap();
+    let volume = program.estimate_volume(&bounds, 0.2, 7)?;
+    assert!(!volume.is_exact());
+    assert!(volume.std_error <= 0.2);
+    assert!((volume.value - 66.0).abs() < 1.0, "volume={volume:?}");
+
+    let again = program.estimate_volume(&bounds, 0.2, 7)?;
+    assert_eq!(volume, again);
+
+    Ok(())
+}
@@ -0,0 +1,198 @@
+use anyhow::Result;
+use range_point_filter::{
+    lanelet2::{from_lanelet2, Lanelet2Options},
+    mesh::MeshFormat,
+    FilterProgram,
+};
+use std::{collections::HashMap, env, fs, path::Path};
+
+#[test]
+fn mesh_export_test() -> Result<()> {
+    let config = fs::read_to_string("tests/box_filter.json5")?;
+    let program: FilterProgram = json5::from_str(&config)?;
+    let dir = env::temp_dir().join(format!("range-point-filter-mesh-{}", std::process::id()));
+    fs::create_dir_all(&dir)?;
+
+    let obj_path = dir.join("box_filter.obj");
+    program.export_mesh(&obj_path, MeshFormat::Obj, 5.0)?;
+    let obj = fs::read_to_string(&obj_path)?;
+    assert!(obj.contains("g box1\nusemtl included"));
+    assert!(obj.contains("g box2\nusemtl excluded"));
+    assert_eq!(
+        obj.lines().filter(|line| line.starts_with("v ")).count(),
+        16
+    );
+    assert_eq!(
+        obj.lines().filter(|line| line.starts_with("f ")).count(),
+        24
+    );
+    assert!(dir.join("box_filter.mtl").exists());
+
+    let ply_path = dir.join("box_filter.ply");
+    program.export_mesh(&ply_path, MeshFormat::Ply, 5.0)?;
+    let ply = fs::read_to_string(&ply_path)?;
+    assert!(ply.contains("element vertex 16"));
+    assert!(ply.contains("element face 24"));
+
+    let gltf_path = dir.join("box_filter.gltf");
+    assert_eq!(MeshFormat::from_path(&gltf_path), Some(MeshFormat::Gltf));
+    program.export_mesh(&gltf_path, MeshFormat::Gltf, 5.0)?;
+    let gltf: serde_json::Value = serde_json::from_str(&fs::read_to_string(&gltf_path)?)?;
+    assert_eq!(gltf["meshes"].as_array().unwrap().len(), 2);
+    assert_eq!(gltf["nodes"][0]["name"], "box1");
+
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+/// Parses the vertices and the triangles of each group in an OBJ
+/// file, where indices are made 0-based.
+fn parse_obj(path: &Path) -> Result<HashMap<String, Vec<[[f64; 3]; 3]>>> {
+    let mut vertices = vec![];
+    let mut groups: HashMap<String, Vec<[[f64; 3]; 3]>> = HashMap::new();
+    let mut group = String::new();
+
+    for line in fs::read_to_string(path)?.lines() {
+        let mut tokens = line.split_whitespace();
+        match tokens.next() {
+            Some("g") => group = tokens.next().unwrap().to_string(),
+            Some("v") => {
+                let values: Vec<f64> = tokens.map(|v| v.parse().unwrap()).collect();
+                vertices.push([values[0], values[1], values[2]]);
+            }
+            Some("f") => {
+                let indices: Vec<usize> = tokens.map(|v| v.parse().unwrap()).collect();
+                let triangle = [0, 1, 2].map(|nth| vertices[indices[nth] - 1]);
+                groups.entry(group.clone()).or_default().push(triangle);
+            }
+            _ => {}
+        }
+    }
+    Ok(groups)
+}
+
+/// The area of the bottom cap, whose triangles must face down.
+fn bottom_area(triangles: &[[[f64; 3]; 3]], z: f64) -> f64 {
+    triangles
+        .iter()
+        .filter(|triangle| triangle.iter().all(|p| p[2] == z))
+        .map(|[a, b, c]| {
+            let cross = (b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0]);
+            assert!(cross <= 0.0, "the bottom face points upwards");
+            -cross / 2.0
+        })
+        .sum()
+}
+
+/// Counts the edges which are not shared by a triangle in the
+/// opposite direction, where the vertices are compared by indices.
+fn open_edges(path: &Path) -> Result<usize> {
+    let mut edges: HashMap<[usize; 2], isize> = HashMap::new();
+    for line in fs::read_to_string(path)?.lines() {
+        let mut tokens = line.split_whitespace();
+        if tokens.next() == Some("f") {
+            let indices: Vec<usize> = tokens.map(|v| v.parse().unwrap()).collect();
+            for nth in 0..3 {
+                let [a, b] = [indices[nth], indices[(nth + 1) % 3]];
+                *edges.entry([a.min(b), a.max(b)]).or_default() += if a < b { 1 } else { -1 };
+            }
+        }
+    }
+    Ok(edges.values().filter(|&&count| count != 0).count())
+}
+
+#[test]
+fn mesh_regions_test() -> Result<()> {
+    let config = fs::read_to_string("tests/mesh_regions.json5")?;
+    let program: FilterProgram = json5::from_str(&config)?;
+    let dir = env::temp_dir().join(format!(
+        "range-point-filter-mesh-regions-{}",
+        std::process::id()
+    ));
+    fs::create_dir_all(&dir)?;
+
+    let obj_path = dir.join("regions.obj");
+    program.export_mesh(&obj_path, MeshFormat::Obj, 5.0)?;
+    let groups = parse_obj(&obj_path)?;
+
+    // The caps of 10 vertices take 10 triangles each, and the walls
+    // take 2 triangles per edge.
+    let plaza = &groups["plaza"];
+    assert_eq!(plaza.len(), 40);
+    assert!((bottom_area(plaza, -2.0) - 15.0).abs() < 1e-9);
+
+    // The corridor is a box along the path and a disc of 16 segments
+    // at each end.
+    let lane = &groups["lane"];
+    assert_eq!(lane.len(), 12 + 2 * (14 * 2 + 16 * 2));
+    assert!(lane.iter().flatten().all(|p| (0.0..=5.0).contains(&p[2])));
+
+    let gltf_path = dir.join("regions.gltf");
+    program.export_mesh(&gltf_path, MeshFormat::Gltf, 5.0)?;
+    let gltf: serde_json::Value = serde_json::from_str(&fs::read_to_string(&gltf_path)?)?;
+    assert_eq!(gltf["meshes"].as_array().unwrap().len(), 2);
+    assert_eq!(gltf["nodes"][0]["name"], "lane");
+    assert_eq!(gltf["accessors"][1]["count"], lane.len() * 3);
+
+    // Programs imported from Lanelet2 consist of polygons.
+    let text = fs::read_to_string("tests/lanelet2.osm")?;
+    let regions = from_lanelet2(&text, &Lanelet2Options::default())?;
+    let program = regions.default_program()?;
+    let program = regions.to_filter_program(program)?;
+    program.export_mesh(dir.join("lanelet2.ply"), MeshFormat::Ply, 5.0)?;
+
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[test]
+fn degenerate_mesh_test() -> Result<()> {
+    // The polygon touches itself at (1, 1), where ear clipping finds
+    // no ear, and the box accepts no points.
+    let program: FilterProgram = json5::from_str(
+        r#"
+{
+    "program": "bowtie + empty",
+    "items": {
+        "bowtie": {
+            "type": "polygon",
+            "vertices": [[0, 0], [2, 0], [1, 1], [2, 2], [0, 2], [1, 1]],
+            "z_min": 0,
+            "z_max": 1
+        },
+        "empty": {
+            "type": "box",
+            "xy": [0, 0],
+            "wh": [-1, -1],
+            "azimuth_degrees": 0
+        }
+    }
+}
+"#,
+    )?;
+    let dir = env::temp_dir().join(format!(
+        "range-point-filter-mesh-degenerate-{}",
+        std::process::id()
+    ));
+    fs::create_dir_all(&dir)?;
+
+    // The remainder is triangulated, so the mesh is closed.
+    let obj_path = dir.join("degenerate.obj");
+    program.export_mesh(&obj_path, MeshFormat::Obj, 5.0)?;
+    let groups = parse_obj(&obj_path)?;
+    assert!(!groups.contains_key("empty"));
+    assert!((bottom_area(&groups["bowtie"], 0.0) - 2.0).abs() < 1e-9);
+    assert_eq!(open_edges(&obj_path)?, 0);
+
+    let gltf_path = dir.join("degenerate.gltf");
+    program.export_mesh(&gltf_path, MeshFormat::Gltf, 5.0)?;
+    let gltf: serde_json::Value = serde_json::from_str(&fs::read_to_string(&gltf_path)?)?;
+    assert_eq!(gltf["meshes"].as_array().unwrap().len(), 1);
+    let accessors = gltf["accessors"].as_array().unwrap();
+    assert!(accessors
+        .iter()
+        .all(|accessor| accessor["count"].as_u64() > Some(0)));
+
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}
@@ -0,0 +1,54 @@
+use anyhow::Result;
+use itertools::iproduct;
+use nalgebra_0_32::Point3;
+use range_point_filter::{FilterProgram, RasterProgram};
+use std::fs;
+
+#[test]
+fn raster_filter_test() -> Result<()> {
+    let config = fs::read_to_string("tests/raster.json5")?;
+    let program: FilterProgram = json5::from_str(&config)?;
+    let raster = program.rasterize(0.5, [-30.0..=30.0, -20.0..=20.0])?;
+    assert!(raster.num_boundary_cells() < raster.shape()[0] * raster.shape()[1] / 4);
+
+    // The lookup table can be stored and loaded back.
+    let raster: RasterProgram = json5::from_str(&json5::to_string(&raster)?)?;
+
+    // The grid covers only part of the samples to exercise the fallback.
+    let coords = (-160..160).map(|v| v as f64 * 0.23);
+    for (x, y) in iproduct!(coords.clone(), coords) {
+        let point = Point3::new(x, y, 0.0);
+        assert_eq!(
+            raster.contains(&point, None),
+            program.contains(&point, None),
+            "point={point}"
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn invalid_raster_test() -> Result<()> {
+    let config = fs::read_to_string("tests/raster.json5")?;
+    let program: FilterProgram = json5::from_str(&config)?;
+    let raster = program.rasterize(0.5, [-30.0..=30.0, -20.0..=20.0])?;
+    let text = serde_json::to_string(&raster)?;
+    let mut value: serde_json::Value = serde_json::from_str(&text)?;
+
+    // A truncated bitmap is rejected instead of panicking on lookup.
+    value["known"].as_array_mut().unwrap().pop();
+    let err = serde_json::from_value::<RasterProgram>(value.clone()).unwrap_err();
+    assert!(err.to_string().contains("known bitmap"), "{err}");
+
+    // The shape must not overflow.
+    value["shape"] = serde_json::json!([usize::MAX, 2]);
+    assert!(serde_json::from_value::<RasterProgram>(value).is_err());
+
+    // A resolution too fine for the bounds is rejected.
+    assert!(program
+        .rasterize(1e-300, [-30.0..=30.0, -20.0..=20.0])
+        .is_err());
+
+    Ok(())
+}
@@ -0,0 +1,39 @@
+use anyhow::Result;
+use nalgebra_0_32::Point3;
+use range_point_filter::FilterProgram;
+use std::fs;
+
+#[test]
+fn schedule_test() -> Result<()> {
+    let config = fs::read_to_string("tests/schedule.json5")?;
+    let program: FilterProgram = json5::from_str(&config)?;
+
+    // 12:00 and 00:00 in UTC+8
+    let noon = 4.0 * 3600.0;
+    let midnight = 16.0 * 3600.0;
+
+    let gate = Point3::new(10.0, 5.0, 0.0);
+    assert!(!program.contains_at(&gate, None, noon));
+    assert!(program.contains_at(&gate, None, midnight));
+    assert!(!program.contains_at(&gate, None, noon + 86400.0 * 3.0));
+
+    // The crane stays at the first and the last keyframes out of the
+    // time span.
+    let start = Point3::new(-40.0, 0.0, 0.0);
+    let middle = Point3::new(0.0, 0.0, 0.0);
+    assert!(!program.contains_at(&start, None, 0.0));
+    assert!(!program.contains_at(&start, None, 100.0));
+    assert!(program.contains_at(&middle, None, 100.0));
+    assert!(!program.contains_at(&middle, None, 150.0));
+    assert!(program.contains_at(&start, None, 150.0));
+
+    // The crane is turned at the end.
+    assert!(!program.contains_at(&Point3::new(40.0, 1.5, 0.0), None, 300.0));
+    assert!(program.contains_at(&Point3::new(41.5, 0.0, 0.0), None, 300.0));
+
+    // Timeless queries ignore schedules and keyframes.
+    assert!(!program.contains(&gate, None));
+    assert!(!program.contains(&middle, None));
+
+    Ok(())
+}
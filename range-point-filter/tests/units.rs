@@ -0,0 +1,79 @@
+use anyhow::Result;
+use nalgebra_0_32::Point3;
+use range_point_filter::{item::PlanarBoxFilterConfig, FilterProgram};
+use std::fs;
+
+#[test]
+fn unit_suffix_test() -> Result<()> {
+    let config = fs::read_to_string("tests/units.json5")?;
+    let program: FilterProgram = json5::from_str(&config)?;
+
+    let text = r#"
+{
+    "program": "box1 + lane",
+    "items": {
+        "box1": {
+            "type": "box",
+            "xy": [3, 2],
+            "wh": [6, 4],
+            "z_min": -1.5,
+            "z": { "<": 3 },
+            "azimuth_degrees": 28.64788975654116
+        },
+        "lane": {
+            "type": "corridor",
+            "path": [[-20, 5], [20, 5]],
+            "width": 3.5
+        }
+    }
+}
+"#;
+    let expect: FilterProgram = json5::from_str(text)?;
+
+    let samples = [
+        Point3::new(3.0, 2.0, 0.0),
+        Point3::new(3.0, 2.0, -1.5),
+        Point3::new(3.0, 2.0, 3.0),
+        Point3::new(5.5, 0.5, 0.0),
+        Point3::new(0.0, 6.7, 0.0),
+        Point3::new(0.0, 6.8, 0.0),
+    ];
+    for point in samples {
+        assert_eq!(
+            program.contains(&point, None),
+            expect.contains(&point, None),
+            "point={point}"
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn unknown_unit_test() {
+    let text = r#"
+{
+    "xy": ["3ft", 0],
+    "wh": [1, 1],
+    "azimuth_degrees": 0
+}
+"#;
+    let err = json5::from_str::<PlanarBoxFilterConfig>(text).unwrap_err();
+    assert!(
+        err.to_string().contains(r#"unknown length unit "ft""#),
+        "{err}"
+    );
+
+    let text = r#"
+{
+    "xy": [0, 0],
+    "wh": [1, 1],
+    "azimuth_degrees": "90m"
+}
+"#;
+    let err = json5::from_str::<PlanarBoxFilterConfig>(text).unwrap_err();
+    assert!(
+        err.to_string().contains(r#"unknown angle unit "m""#),
+        "{err}"
+    );
+}
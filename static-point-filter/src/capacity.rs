@@ -0,0 +1,159 @@
+use crate::{
+    history_words, hysteresis::VoxelState, Eviction, Inner, Neighborhood, StaticPointFilter, Voxel,
+};
+use dashmap::DashMap;
+use std::{mem, sync::atomic::Ordering::*};
+
+/// The ratio of the voxel cap evicted at once when the voxel map is
+/// full, so that new voxels can be admitted in later frames.
+const EVICTION_RATIO: f64 = 0.1;
+
+/// The memory usage of the voxel map.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryStats {
+    /// The number of voxels in the map.
+    pub voxels: usize,
+    /// The maximum number of voxels if it is limited.
+    pub max_voxels: Option<usize>,
+    /// The number of voxels evicted so far.
+    pub evicted: u64,
+    /// The number of points in new voxels which are refused since the
+    /// map is full.
+    pub refused: u64,
+    /// The estimated size of voxel entries in bytes, including the
+    /// history of long sliding windows.
+    pub estimated_bytes: usize,
+}
+
+impl StaticPointFilter {
+    pub fn memory_stats(&self) -> MemoryStats {
+        let inner = self.inner.read().unwrap();
+        let voxels = inner.voxels.len();
+        let words: usize = if history_words(&self.config.model) > 0 {
+            inner
+                .voxels
+                .iter()
+                .map(|entry| entry.value().history.len())
+                .sum()
+        } else {
+            0
+        };
+
+        MemoryStats {
+            voxels,
+            max_voxels: self.config.max_voxels.map(|max| max.get()),
+            evicted: self.evicted.load(Acquire),
+            refused: self.refused.load(Acquire),
+            estimated_bytes: voxels * (mem::size_of::<[i64; 3]>() + mem::size_of::<Voxel>())
+                + words * mem::size_of::<u64>(),
+        }
+    }
+
+    pub(crate) fn is_full(&self, voxels: &DashMap<[i64; 3], Voxel>) -> bool {
+        self.config
+            .max_voxels
+            .is_some_and(|max| voxels.len() >= max.get())
+    }
+
+    /// Evicts voxels if the voxel map is full. Voxels are evicted only
+    /// if no point in them or in the voxels whose neighborhood they
+    /// belong to may be background, so that no point changes from
+    /// background to foreground due to eviction. An evicted voxel
+    /// restarts counting if it is observed again.
+    pub(crate) fn evict(&self, inner: &mut Inner) {
+        let Some(max) = self.config.max_voxels else {
+            return;
+        };
+        if !self.is_full(&inner.voxels) {
+            return;
+        }
+
+        let max = max.get();
+        let target = max - (max as f64 * EVICTION_RATIO).ceil() as usize;
+        let excess = inner.voxels.len() - target;
+
+        let mut candidates: Vec<([i64; 3], f64)> = inner
+            .voxels
+            .iter()
+            .filter_map(|entry| {
+                let Voxel {
+                    count,
+                    bits,
+                    last_seen,
+                    ..
+                } = entry.value();
+                let occupancy = self.occupancy(count.load(Acquire), bits.load(Acquire));
+                if self.near_background(inner, *entry.key()) {
+                    return None;
+                }
+
+                let key = match self.config.eviction {
+                    Eviction::LeastRecentlyObserved => last_seen.load(Acquire) as f64,
+                    Eviction::LowestOccupancy => occupancy,
+                };
+                Some((*entry.key(), key))
+            })
+            .collect();
+
+        if candidates.len() > excess {
+            candidates.select_nth_unstable_by(excess, |(_, lhs), (_, rhs)| lhs.total_cmp(rhs));
+            candidates.truncate(excess);
+        }
+
+        for (index, _) in &candidates {
+            inner.voxels.remove(index);
+        }
+        self.evicted.fetch_add(candidates.len() as u64, Release);
+    }
+
+    /// Checks if a point in the voxel, or in a voxel whose
+    /// neighborhood includes it, may be background.
+    fn near_background(&self, inner: &Inner, index: [i64; 3]) -> bool {
+        if self.config.neighborhood == Neighborhood::Single {
+            return self.may_be_background(inner, index);
+        }
+        // Both adjacent and trilinear neighborhoods are within one
+        // voxel along each axis.
+        block(index).any(|neighbor| self.may_be_background(inner, neighbor))
+    }
+
+    /// Checks if a point in the voxel may be background.
+    fn may_be_background(&self, inner: &Inner, index: [i64; 3]) -> bool {
+        let occupancy = |index| match inner.voxels.get(&index) {
+            Some(entry) => {
+                let Voxel { count, bits, .. } = entry.value();
+                self.occupancy(count.load(Acquire), bits.load(Acquire))
+            }
+            None => 0.0,
+        };
+
+        if self.config.hysteresis.is_some() {
+            // Voxels start as background.
+            return inner.voxels.get(&index).is_none_or(|entry| {
+                !VoxelState::unpack(entry.value().state.load(Acquire)).foreground
+            });
+        }
+
+        match self.config.neighborhood {
+            Neighborhood::Single => occupancy(index) >= inner.threshold,
+            Neighborhood::Adjacent { .. } => {
+                let coords = index.map(|index| index as f64 + 0.5);
+                self.neighborhood_occupancy(&inner.voxels, coords, occupancy(index))
+                    >= inner.threshold
+            }
+            // The interpolated occupancy is at most the largest
+            // occupancy of the voxels around.
+            Neighborhood::Trilinear { .. } => {
+                block(index).any(|neighbor| occupancy(neighbor) >= inner.threshold)
+            }
+        }
+    }
+}
+
+/// The voxel and the 26 voxels around it.
+fn block(index: [i64; 3]) -> impl Iterator<Item = [i64; 3]> {
+    (0..27).map(move |code: i64| {
+        let offset = [code % 3 - 1, code / 3 % 3 - 1, code / 9 - 1];
+        std::array::from_fn(|axis| index[axis] + offset[axis])
+    })
+}
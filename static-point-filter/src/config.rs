@@ -1,12 +1,43 @@
 use noisy_float::prelude::*;
-use serde::{Deserialize, Serialize};
-use std::ops::RangeInclusive;
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize};
+use std::{
+    num::{NonZeroU32, NonZeroUsize},
+    ops::RangeInclusive,
+};
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "with-schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(
+    feature = "with-schemars",
+    schemars(rename = "StaticPointFilterConfig")
+)]
 pub struct Config {
     pub range: Range,
     pub voxel_size: VoxelSize,
+    #[cfg_attr(feature = "with-schemars", schemars(with = "f64"))]
     pub background_threshold: R64,
+    #[serde(default)]
+    pub out_of_range: OutOfRange,
+    #[serde(default)]
+    pub model: Model,
+    /// The initial operating mode.
+    #[serde(default)]
+    pub mode: Mode,
+    /// The maximum number of voxels kept in memory. Voxels are not
+    /// limited if it is not set.
+    #[serde(default)]
+    pub max_voxels: Option<NonZeroUsize>,
+    /// The voxels evicted first when the voxel map is full.
+    #[serde(default)]
+    pub eviction: Eviction,
+    /// The voxels around the point which decide background.
+    #[serde(default)]
+    pub neighborhood: Neighborhood,
+    /// Delays voxels switching between background and foreground.
+    /// Voxels are classified by the occupancy of the current frame if
+    /// it is not set.
+    #[serde(default)]
+    pub hysteresis: Option<Hysteresis>,
 }
 
 impl Config {
@@ -16,18 +47,234 @@ impl Config {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "with-schemars", derive(schemars::JsonSchema))]
 pub struct Range {
     #[serde(with = "crate::utils::serde_bound")]
+    #[cfg_attr(feature = "with-schemars", schemars(with = "[f64; 2]"))]
     pub x_bound: RangeInclusive<R64>,
     #[serde(with = "crate::utils::serde_bound")]
+    #[cfg_attr(feature = "with-schemars", schemars(with = "[f64; 2]"))]
     pub y_bound: RangeInclusive<R64>,
     #[serde(with = "crate::utils::serde_bound")]
+    #[cfg_attr(feature = "with-schemars", schemars(with = "[f64; 2]"))]
     pub z_bound: RangeInclusive<R64>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "with-schemars", derive(schemars::JsonSchema))]
 pub struct VoxelSize {
+    #[cfg_attr(feature = "with-schemars", schemars(with = "f64"))]
     pub x_size: R64,
+    #[cfg_attr(feature = "with-schemars", schemars(with = "f64"))]
     pub y_size: R64,
+    #[cfg_attr(feature = "with-schemars", schemars(with = "f64"))]
     pub z_size: R64,
 }
+
+/// The treatment of points out of the range.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "with-schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum OutOfRange {
+    /// Report the points as background, so that they are dropped.
+    #[default]
+    Drop,
+    /// Report the points as foreground, so that they are kept.
+    Keep,
+    /// Do not crop the points. The voxel grid extends beyond the range
+    /// in all directions, and the range only sets the grid origin at
+    /// its lower corner.
+    Ignore,
+}
+
+/// The voxels whose occupancy decides whether a point is background.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "with-schemars", derive(schemars::JsonSchema))]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Neighborhood {
+    /// Use the voxel of the point alone.
+    #[default]
+    Single,
+    /// Aggregate the occupancy of the voxel and its adjacent voxels.
+    Adjacent {
+        /// The number of adjacent voxels, which is 6 for voxels
+        /// sharing faces, 18 for voxels sharing edges, or 26 for
+        /// voxels sharing corners.
+        #[serde(deserialize_with = "connectivity")]
+        connectivity: u8,
+        aggregation: Aggregation,
+        /// The weights of adjacent voxels along the x, y and z axes.
+        /// The weight of a voxel is the product of the weights of the
+        /// axes it is offset along. Axes weighted zero are skipped.
+        #[serde(default = "ones", deserialize_with = "axis_weights")]
+        #[cfg_attr(feature = "with-schemars", schemars(with = "[f64; 3]"))]
+        axis_weights: [R64; 3],
+    },
+    /// Interpolate the occupancy of the 8 voxels around the point by
+    /// its position within the voxel.
+    Trilinear {
+        /// Whether to interpolate along the x, y and z axes. Disabled
+        /// axes use the voxel of the point alone.
+        #[serde(default = "all")]
+        axes: [bool; 3],
+    },
+}
+
+/// The aggregation of the occupancy of adjacent voxels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "with-schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum Aggregation {
+    /// The maximum of the weighted occupancy.
+    Max,
+    /// The weighted sum of occupancy divided by the total weight.
+    WeightedSum,
+}
+
+fn connectivity<'de, D>(deserializer: D) -> Result<u8, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let connectivity = u8::deserialize(deserializer)?;
+    if ![6, 18, 26].contains(&connectivity) {
+        return Err(D::Error::custom(format!(
+            "the connectivity must be 6, 18 or 26, but get {connectivity}"
+        )));
+    }
+    Ok(connectivity)
+}
+
+fn axis_weights<'de, D>(deserializer: D) -> Result<[R64; 3], D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let weights = <[R64; 3]>::deserialize(deserializer)?;
+    if weights.iter().any(|weight| *weight < 0.0) {
+        return Err(D::Error::custom("the axis weights must not be negative"));
+    }
+    Ok(weights)
+}
+
+fn ones() -> [R64; 3] {
+    [r64(1.0); 3]
+}
+
+fn all() -> [bool; 3] {
+    [true; 3]
+}
+
+/// The hysteresis of voxel states. A voxel switches between
+/// background and foreground only after consecutive frames of
+/// evidence. The thresholds are ratios of frames like the
+/// `background_threshold`, which they replace in classification.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "with-schemars", derive(schemars::JsonSchema))]
+pub struct Hysteresis {
+    /// The occupancy at which a foreground voxel gains evidence of
+    /// being background.
+    #[cfg_attr(feature = "with-schemars", schemars(with = "f64"))]
+    pub promote_threshold: R64,
+    /// The number of consecutive frames of evidence for a foreground
+    /// voxel to become background.
+    pub promote_frames: NonZeroU32,
+    /// The occupancy below which an occupied background voxel gains
+    /// evidence of being foreground.
+    #[cfg_attr(feature = "with-schemars", schemars(with = "f64"))]
+    pub demote_threshold: R64,
+    /// The number of consecutive frames of evidence for a background
+    /// voxel to become foreground.
+    pub demote_frames: NonZeroU32,
+    /// The number of frames after a voxel becomes foreground, during
+    /// which it gains no evidence of being background.
+    #[serde(default)]
+    pub persistence_frames: u32,
+}
+
+/// The order to evict voxels when the voxel map is full. Background
+/// voxels are never evicted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "with-schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum Eviction {
+    /// Evict voxels observed least recently.
+    #[default]
+    LeastRecentlyObserved,
+    /// Evict voxels with the lowest occupancy.
+    LowestOccupancy,
+}
+
+/// The operating mode of the filter.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "with-schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum Mode {
+    /// Record point occurrences, and report all points as background
+    /// while the background is being learned.
+    Learning,
+    /// Classify points by the background learned so far, without
+    /// recording anything.
+    Frozen,
+    /// Record point occurrences and classify points at the same time.
+    #[default]
+    Adaptive,
+}
+
+/// The statistical model which decides background voxels. All models
+/// share the voxel map and the `background_threshold`, which is the
+/// ratio of the frames where a voxel is occupied.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "with-schemars", derive(schemars::JsonSchema))]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Model {
+    /// Count the occupancy since startup.
+    #[default]
+    Cumulative,
+    /// Count the occupancy in the recent frames.
+    SlidingWindow {
+        /// The number of frames in the window, at most 65536.
+        /// Windows longer than 64 frames keep extra history per
+        /// voxel.
+        #[serde(deserialize_with = "window")]
+        window: NonZeroUsize,
+    },
+    /// Weigh the occupancy of past frames by exponential decay.
+    ExponentialDecay {
+        /// The number of frames after which the weight halves.
+        #[serde(deserialize_with = "positive")]
+        #[cfg_attr(feature = "with-schemars", schemars(with = "f64"))]
+        half_life: R64,
+    },
+    /// Count the occupancy since the last reset, and forget all
+    /// voxels every `period` frames.
+    PeriodicReset { period: NonZeroUsize },
+}
+
+/// The longest sliding window, which takes 8 KiB of history per
+/// voxel.
+const MAX_WINDOW: usize = 1 << 16;
+
+fn window<'de, D>(deserializer: D) -> Result<NonZeroUsize, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let window = usize::deserialize(deserializer)?;
+    let Some(window) = NonZeroUsize::new(window).filter(|window| window.get() <= MAX_WINDOW) else {
+        return Err(D::Error::custom(format!(
+            "the window must be within 1 to {MAX_WINDOW} frames, but get {window}"
+        )));
+    };
+    Ok(window)
+}
+
+fn positive<'de, D>(deserializer: D) -> Result<R64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = R64::deserialize(deserializer)?;
+    if value <= 0.0 {
+        return Err(D::Error::custom(format!(
+            "the value must be positive, but get {value}"
+        )));
+    }
+    Ok(value)
+}
@@ -0,0 +1,88 @@
+use crate::{config, frames, hysteresis::VoxelState, threshold_at, StaticPointFilter, Voxel};
+use nalgebra::Point3;
+use std::sync::atomic::Ordering::*;
+
+/// A voxel classified as background.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BackgroundVoxel {
+    /// The index of the voxel counted from the lower corner of the
+    /// range.
+    pub index: [i64; 3],
+    /// The center of the voxel.
+    pub center: Point3<f64>,
+    /// The ratio of frames where the voxel is occupied.
+    pub occupancy: f64,
+}
+
+impl StaticPointFilter {
+    /// Lists voxels in the map whose centers are background with the
+    /// `threshold` ratio of frames, sorted by indices. The occupancy
+    /// is aggregated over the neighborhood as in classification, so
+    /// passing the `background_threshold` of the config lists the
+    /// voxels whose centers are currently classified as background.
+    /// Empty voxels which are background by their neighbors are not
+    /// listed.
+    ///
+    /// With the hysteresis, voxels are classified by their states, so
+    /// the voxels in the background state are listed regardless of
+    /// the `threshold`.
+    pub fn export_background(&self, threshold: f64) -> Vec<BackgroundVoxel> {
+        let config::Range {
+            x_bound,
+            y_bound,
+            z_bound,
+        } = &self.config.range;
+        let config::VoxelSize {
+            x_size,
+            y_size,
+            z_size,
+        } = &self.config.voxel_size;
+        let origin = [x_bound, y_bound, z_bound].map(|bound| bound.start().raw());
+        let sizes = [x_size, y_size, z_size].map(|size| size.raw());
+
+        let inner = self.inner.read().unwrap();
+        let frames = frames(&self.config, inner.step);
+        let min_occupancy = threshold_at(&self.config, inner.step, threshold);
+
+        let mut voxels: Vec<_> = inner
+            .voxels
+            .iter()
+            .filter_map(|entry| {
+                let Voxel {
+                    count, bits, state, ..
+                } = entry.value();
+                let occupancy = self.occupancy(count.load(Acquire), bits.load(Acquire));
+                let is_background = if self.config.hysteresis.is_some() {
+                    !VoxelState::unpack(state.load(Acquire)).foreground
+                } else {
+                    let coords = entry.key().map(|key| key as f64 + 0.5);
+                    self.neighborhood_occupancy(&inner.voxels, coords, occupancy) >= min_occupancy
+                };
+                if !is_background {
+                    return None;
+                }
+
+                let key = entry.key();
+                let index: [i64; 3] = std::array::from_fn(|axis| key[axis] - self.shift[axis]);
+                let center: [f64; 3] = std::array::from_fn(|axis| {
+                    origin[axis] + (index[axis] as f64 + 0.5) * sizes[axis]
+                });
+                // The current frame is not finished, so the occupancy
+                // can exceed the number of frames.
+                let occupancy = if frames > 0.0 {
+                    (occupancy / frames).min(1.0)
+                } else {
+                    1.0
+                };
+
+                Some(BackgroundVoxel {
+                    index,
+                    center: center.into(),
+                    occupancy,
+                })
+            })
+            .collect();
+        voxels.sort_unstable_by_key(|voxel| voxel.index);
+        voxels
+    }
+}
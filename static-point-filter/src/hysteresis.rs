@@ -0,0 +1,145 @@
+use crate::{threshold_at, Hysteresis, Inner, Neighborhood, StaticPointFilter, Voxel};
+use dashmap::DashMap;
+use std::sync::atomic::Ordering::*;
+
+/// The most consecutive frames of evidence which are counted.
+const MAX_STREAK: u32 = u32::MAX >> 1;
+
+/// The hysteresis state of a voxel, which is packed in a `u64` so
+/// that it is updated in place. Voxels start as background.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct VoxelState {
+    pub foreground: bool,
+    /// The number of consecutive frames of evidence to switch.
+    streak: u32,
+    /// The lower 32 bits of the step when the voxel switched last.
+    since: u32,
+}
+
+impl VoxelState {
+    pub fn unpack(bits: u64) -> Self {
+        Self {
+            foreground: bits & 1 == 1,
+            streak: (bits >> 1) as u32 & MAX_STREAK,
+            since: (bits >> 32) as u32,
+        }
+    }
+
+    /// Checks if the state is the same as a new voxel. The step of
+    /// the last switch is only used in the foreground.
+    pub fn is_idle(self) -> bool {
+        !self.foreground && self.streak == 0
+    }
+
+    pub fn pack(self) -> u64 {
+        self.foreground as u64 | (self.streak as u64) << 1 | (self.since as u64) << 32
+    }
+
+    /// Counts the evidence of the frame at the step, where the voxel
+    /// has the occupancy including the frame, and switches the state
+    /// once the evidence lasts for enough frames.
+    fn next(
+        self,
+        hysteresis: &Hysteresis,
+        step: usize,
+        occupancy: f64,
+        occupied: bool,
+        threshold: impl Fn(f64) -> f64,
+    ) -> Self {
+        let Hysteresis {
+            promote_threshold,
+            promote_frames,
+            demote_threshold,
+            demote_frames,
+            persistence_frames,
+        } = *hysteresis;
+
+        let (evidence, frames) = if self.foreground {
+            let elapsed = (step as u32).wrapping_sub(self.since);
+            let evidence =
+                elapsed >= persistence_frames && occupancy >= threshold(promote_threshold.raw());
+            (evidence, promote_frames)
+        } else {
+            let evidence = occupied && occupancy < threshold(demote_threshold.raw());
+            (evidence, demote_frames)
+        };
+
+        if !evidence {
+            return Self { streak: 0, ..self };
+        }
+        let streak = (self.streak + 1).min(MAX_STREAK);
+        if streak < frames.get() {
+            return Self { streak, ..self };
+        }
+        Self {
+            foreground: !self.foreground,
+            streak: 0,
+            since: step as u32,
+        }
+    }
+}
+
+impl StaticPointFilter {
+    /// Classifies the voxel by its hysteresis state, counting the
+    /// current frame where the voxel is occupied as evidence. `center`
+    /// is the occupancy of the voxel including the current frame.
+    pub(crate) fn hysteresis_is_background(
+        &self,
+        hysteresis: &Hysteresis,
+        inner: &Inner,
+        index: [i64; 3],
+        state: u64,
+        center: f64,
+    ) -> bool {
+        let occupancy = self.evidence_occupancy(&inner.voxels, index, center);
+        let state =
+            VoxelState::unpack(state).next(hysteresis, inner.step, occupancy, true, |ratio| {
+                threshold_at(&self.config, inner.step + 1, ratio)
+            });
+        !state.foreground
+    }
+
+    /// Counts the evidence of the current frame for all voxels. It is
+    /// called before the statistics are stepped.
+    pub(crate) fn update_hysteresis(&self, inner: &Inner) {
+        let Some(hysteresis) = &self.config.hysteresis else {
+            return;
+        };
+        let Inner {
+            voxels, step, mask, ..
+        } = inner;
+
+        for entry in voxels.iter() {
+            let Voxel {
+                count, bits, state, ..
+            } = entry.value();
+            let bits = bits.load(Acquire);
+            let center = self.occupancy(count.load(Acquire), bits);
+            let occupancy = self.evidence_occupancy(voxels, *entry.key(), center);
+            let next = VoxelState::unpack(state.load(Acquire)).next(
+                hysteresis,
+                *step,
+                occupancy,
+                bits & mask != 0,
+                |ratio| threshold_at(&self.config, step + 1, ratio),
+            );
+            state.store(next.pack(), Release);
+        }
+    }
+
+    /// Computes the occupancy around the voxel center, where the voxel
+    /// has the given occupancy. Evidence is taken per voxel, so it does
+    /// not depend on the position of points in the voxel.
+    fn evidence_occupancy(
+        &self,
+        voxels: &DashMap<[i64; 3], Voxel>,
+        index: [i64; 3],
+        center: f64,
+    ) -> f64 {
+        if self.config.neighborhood == Neighborhood::Single {
+            return center;
+        }
+        let coords = index.map(|index| index as f64 + 0.5);
+        self.neighborhood_occupancy(voxels, coords, center)
+    }
+}
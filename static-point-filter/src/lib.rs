@@ -1,29 +1,63 @@
+mod capacity;
 mod config;
+mod export;
+mod hysteresis;
+mod mode;
+mod neighborhood;
+mod range_image;
+mod state;
 mod utils;
 
-use config::Config;
+use arc_swap::ArcSwapOption;
+pub use capacity::MemoryStats;
+pub use config::{
+    Aggregation, Config, Eviction, Hysteresis, Mode, Model, Neighborhood, OutOfRange, Range,
+    VoxelSize,
+};
 use dashmap::DashMap;
+pub use export::BackgroundVoxel;
+use hysteresis::VoxelState;
+use mode::Frozen;
 use nalgebra::Point3;
 use noisy_float::prelude::*;
+pub use range_image::{RangeImageConfig, RangeImageFilter, SensorPose};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
-use std::sync::{
-    atomic::{AtomicU64, Ordering::*},
-    RwLock,
+use std::{
+    num::NonZeroUsize,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering::*},
+        Arc, RwLock,
+    },
 };
 
 #[derive(Debug)]
 pub struct StaticPointFilter {
     config: Config,
-    inner: RwLock<Inner>,
+    /// The statistics, which are shared with the filters migrated
+    /// from this one.
+    inner: Arc<RwLock<Inner>>,
+    /// The offsets from the voxel indices of the grid to the keys of
+    /// the voxel map. They are nonzero if the filter is migrated to a
+    /// shifted range.
+    shift: [i64; 3],
+    /// The background voxels if the filter is frozen.
+    frozen: ArcSwapOption<Frozen>,
+    learning: AtomicBool,
+    /// The number of voxels evicted, and the number of new voxels
+    /// refused since the voxel map is full.
+    evicted: Arc<AtomicU64>,
+    refused: Arc<AtomicU64>,
 }
 
 impl Clone for StaticPointFilter {
     fn clone(&self) -> Self {
-        let inner = self.inner.read().unwrap();
-        Self {
-            config: self.config.clone(),
-            inner: RwLock::new(inner.clone()),
-        }
+        let inner = self.inner.read().unwrap().clone();
+        let mut filter = Self::from_inner(&self.config, inner);
+        filter.shift = self.shift;
+        filter.set_mode(self.mode());
+        filter.evicted.store(self.evicted.load(Acquire), Release);
+        filter.refused.store(self.refused.load(Acquire), Release);
+        filter
     }
 }
 
@@ -46,71 +80,392 @@ impl<'de> Deserialize<'de> for StaticPointFilter {
     }
 }
 
+#[cfg(feature = "with-schemars")]
+impl schemars::JsonSchema for StaticPointFilter {
+    fn schema_name() -> String {
+        Config::schema_name()
+    }
+
+    fn json_schema(gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        Config::json_schema(gen)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Inner {
-    voxels: DashMap<[usize; 3], Voxel>,
+    voxels: DashMap<[i64; 3], Voxel>,
     step: usize,
     mask: u64,
-    threshold: u64,
+    threshold: f64,
 }
 
+/// The occupancy statistics of a voxel. The bits record the frames
+/// where the voxel is occupied. The count accumulates the occupancy
+/// of earlier frames, or keeps the decayed score in `f64` bits for
+/// the exponential decay model. The step when the voxel is observed
+/// last is kept for eviction. The packed hysteresis state is only
+/// updated at steps.
+///
+/// Sliding windows longer than 64 frames move the bits to the
+/// history ring every 64 frames, and the count keeps the occupancy
+/// in the history. The history is only accessed at steps, so that
+/// classification never touches it.
 #[derive(Debug, Default)]
 struct Voxel {
     bits: AtomicU64,
     count: AtomicU64,
+    last_seen: AtomicU64,
+    state: AtomicU64,
+    history: Box<[u64]>,
+}
+
+impl Voxel {
+    /// Checks if the hysteresis state is the same as a new voxel, so
+    /// that the voxel can be forgotten once it becomes empty.
+    fn is_idle(&mut self) -> bool {
+        VoxelState::unpack(*self.state.get_mut()).is_idle()
+    }
 }
 
 impl Clone for Voxel {
     fn clone(&self) -> Self {
-        let Self { bits, count } = self;
+        let Self {
+            bits,
+            count,
+            last_seen,
+            state,
+            history,
+        } = self;
         let bits = bits.load(Acquire);
         let count = count.load(Acquire);
+        let last_seen = last_seen.load(Acquire);
+        let state = state.load(Acquire);
         Self {
             bits: AtomicU64::new(bits),
             count: AtomicU64::new(count),
+            last_seen: AtomicU64::new(last_seen),
+            state: AtomicU64::new(state),
+            history: history.clone(),
         }
     }
 }
 
 impl StaticPointFilter {
     pub fn new(config: &Config) -> Self {
+        let inner = Inner {
+            voxels: DashMap::new(),
+            step: 0,
+            mask: 1,
+            threshold: 0.0,
+        };
+        let filter = Self::from_inner(config, inner);
+        filter.set_mode(config.mode);
+        filter
+    }
+
+    fn from_inner(config: &Config, inner: Inner) -> Self {
         Self {
             config: config.clone(),
-            inner: RwLock::new(Inner {
-                voxels: DashMap::new(),
-                step: 0,
-                mask: 1,
-                threshold: 0,
-            }),
+            inner: Arc::new(RwLock::new(inner)),
+            shift: [0; 3],
+            frozen: ArcSwapOption::empty(),
+            learning: AtomicBool::new(false),
+            evicted: Arc::default(),
+            refused: Arc::default(),
         }
     }
 
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Builds a filter with another config which keeps the learned
+    /// statistics. It returns `None` if the voxel grids are not
+    /// aligned, that is, the voxel sizes differ or the ranges are
+    /// not offset by whole voxels, or if the models differ. The filter
+    /// starts in the mode of the new config.
+    ///
+    /// The statistics are shared with this filter rather than copied,
+    /// so that points observed by this filter meanwhile are kept, and
+    /// the threshold of the new config applies to both. Voxels out of
+    /// the new range are dropped, unless the new config ignores the
+    /// range. This filter should be discarded afterwards.
+    pub fn migrate(&self, config: &Config) -> Option<Self> {
+        let old = &self.config;
+        if old.model != config.model {
+            return None;
+        }
+        let sizes = |config: &Config| {
+            let config::VoxelSize {
+                x_size,
+                y_size,
+                z_size,
+            } = config.voxel_size;
+            [x_size, y_size, z_size].map(|size| size.raw())
+        };
+        let bounds = |config: &Config| {
+            let config::Range {
+                x_bound,
+                y_bound,
+                z_bound,
+            } = &config.range;
+            [x_bound, y_bound, z_bound].map(|bound| (bound.start().raw(), bound.end().raw()))
+        };
+
+        let new_sizes = sizes(config);
+        if sizes(old) != new_sizes {
+            return None;
+        }
+
+        // The shifts from the new grid to the keys of the voxel map,
+        // and the maximum indices of the new grid.
+        let mut shift = [0i64; 3];
+        let mut limits = [0i64; 3];
+        for (axis, ((old_start, _), (start, end))) in
+            bounds(old).into_iter().zip(bounds(config)).enumerate()
+        {
+            let size = new_sizes[axis];
+            let offset = (old_start - start) / size;
+            if (offset - offset.round()).abs() > 1e-6 {
+                return None;
+            }
+            shift[axis] = self.shift[axis] - offset.round() as i64;
+            limits[axis] = ((end - start) / size).floor() as i64;
+        }
+
+        // Observations wait for the voxels to be dropped and the
+        // threshold to be updated.
+        let mut inner = self.inner.write().unwrap();
+        if config.out_of_range != OutOfRange::Ignore {
+            inner.voxels.retain(|key, _| {
+                (0..3).all(|axis| (0..=limits[axis]).contains(&(key[axis] - shift[axis])))
+            });
+        }
+        inner.threshold = threshold(config, inner.step);
+        drop(inner);
+
+        let filter = Self {
+            config: config.clone(),
+            inner: self.inner.clone(),
+            shift,
+            frozen: ArcSwapOption::empty(),
+            learning: AtomicBool::new(false),
+            evicted: self.evicted.clone(),
+            refused: self.refused.clone(),
+        };
+        filter.set_mode(config.mode);
+        Some(filter)
+    }
+
+    /// Advances to the next frame. It has no effect if the filter is
+    /// frozen.
     pub fn step(&self) {
-        let background_threshold = self.config.background_threshold.raw();
+        if self.frozen.load().is_some() {
+            return;
+        }
+
+        let mut inner = self.inner.write().unwrap();
+        self.update_hysteresis(&inner);
         let Inner {
             step,
             threshold,
             mask,
             voxels,
-            ..
-        } = &mut *self.inner.write().unwrap();
+        } = &mut *inner;
 
         *step += 1;
-        *threshold = (*step as f64 * background_threshold).ceil() as u64;
 
-        if *step % u64::BITS as usize == 0 {
-            voxels.iter().for_each(|entry| {
-                let Voxel { count, bits } = entry.value();
-                let addition = bits.swap(0, Acquire).count_ones() as u64;
-                count.fetch_add(addition, Release);
-            });
-            *mask = 1;
-        } else {
-            *mask <<= 1;
+        match self.config.model {
+            Model::Cumulative => accumulate(voxels, *step, mask),
+            Model::PeriodicReset { period } => {
+                if *step % period.get() == 0 {
+                    // Keep the hysteresis states, which are not
+                    // reset with the occupancy.
+                    voxels.retain(|_, voxel| {
+                        *voxel.bits.get_mut() = 0;
+                        *voxel.count.get_mut() = 0;
+                        !voxel.is_idle()
+                    });
+                    *step = 0;
+                    *mask = 1;
+                } else {
+                    accumulate(voxels, *step, mask);
+                }
+            }
+            Model::SlidingWindow { window } if window.get() > u64::BITS as usize => {
+                slide(voxels, *step, mask, window);
+            }
+            Model::SlidingWindow { window } => {
+                // The bits form a ring buffer of the recent frames.
+                // Clear the bit of the oldest frame for the new one,
+                // and forget voxels which become empty.
+                *mask = 1 << (*step % window.get());
+                let mask = *mask;
+                voxels.retain(|_, voxel| {
+                    let bits = voxel.bits.get_mut();
+                    *bits &= !mask;
+                    *bits != 0 || !voxel.is_idle()
+                });
+            }
+            Model::ExponentialDecay { half_life } => {
+                let decay = decay(half_life.raw());
+                voxels.retain(|_, voxel| {
+                    let Voxel { bits, count, .. } = voxel;
+                    let occupied = std::mem::take(bits.get_mut()) != 0;
+                    let score = f64::from_bits(*count.get_mut()) * decay + occupied as u8 as f64;
+                    *count.get_mut() = score.to_bits();
+                    score >= MIN_SCORE || !voxel.is_idle()
+                });
+            }
         }
+
+        *threshold = crate::threshold(&self.config, *step);
+
+        self.evict(&mut inner);
     }
 
+    /// Checks if the point is in a background voxel, and records the
+    /// point occurrence in the current frame unless the filter is
+    /// frozen.
     pub fn check_is_background(&self, point: &Point3<f64>) -> bool {
+        if let Some(is_background) = self.check_frozen(point) {
+            return is_background;
+        }
+
+        let inner = self.inner.read().unwrap();
+        let Inner {
+            ref voxels,
+            step,
+            mask,
+            ..
+        } = *inner;
+
+        let Some(coords) = self.voxel_coords(point) else {
+            return self.learning.load(Relaxed) || self.is_dropped(point);
+        };
+        let index = voxel_index(coords);
+
+        // Refuse new voxels if the voxel map is full. The point is
+        // classified as in an empty voxel.
+        if self.is_full(voxels) && !voxels.contains_key(&index) {
+            self.refused.fetch_add(1, Relaxed);
+            return self.learning.load(Relaxed) || self.classify(&inner, coords, 0, 0, 0);
+        }
+
+        let entry = voxels.entry(index).or_insert_with(Voxel::default);
+        let Voxel {
+            count,
+            bits,
+            last_seen,
+            state,
+            ..
+        } = entry.value();
+        let bits = bits.fetch_or(mask, Relaxed);
+        last_seen.store(step as u64, Relaxed);
+
+        if self.learning.load(Relaxed) {
+            return true;
+        }
+
+        // Release the entry before looking up neighbors in the same
+        // shard.
+        let (count, state) = (count.load(Relaxed), state.load(Relaxed));
+        drop(entry);
+        self.classify(&inner, coords, count, bits, state)
+    }
+
+    /// Checks if the point would be classified as background by
+    /// [check_is_background](Self::check_is_background) without
+    /// updating the statistics.
+    pub fn is_background(&self, point: &Point3<f64>) -> bool {
+        if let Some(is_background) = self.check_frozen(point) {
+            return is_background;
+        }
+        if self.learning.load(Relaxed) {
+            return true;
+        }
+
+        let inner = self.inner.read().unwrap();
+        let Some(coords) = self.voxel_coords(point) else {
+            return self.is_dropped(point);
+        };
+
+        // A missing voxel would be inserted empty.
+        let (count, bits, state) = match inner.voxels.get(&voxel_index(coords)) {
+            Some(entry) => {
+                let Voxel {
+                    count, bits, state, ..
+                } = entry.value();
+                (count.load(Relaxed), bits.load(Relaxed), state.load(Relaxed))
+            }
+            None => (0, 0, 0),
+        };
+        self.classify(&inner, coords, count, bits, state)
+    }
+
+    /// Classifies the point at the voxel coordinates, whose voxel has
+    /// the count, the bits before recording the point and the packed
+    /// hysteresis state. Both
+    /// [check_is_background](Self::check_is_background) and
+    /// [is_background](Self::is_background) decide by it.
+    fn classify(&self, inner: &Inner, coords: [f64; 3], count: u64, bits: u64, state: u64) -> bool {
+        // The hysteresis counts the current frame as evidence, and
+        // compares it with the thresholds including the frame.
+        if let Some(hysteresis) = &self.config.hysteresis {
+            let center = self.occupancy(count, bits | inner.mask);
+            let index = voxel_index(coords);
+            return self.hysteresis_is_background(hysteresis, inner, index, state, center);
+        }
+
+        let center = self.occupancy(count, bits);
+        self.neighborhood_occupancy(&inner.voxels, coords, center) >= inner.threshold
+    }
+
+    /// Computes the occupancy around the point by the neighborhood
+    /// config, where the voxel of the point has the given occupancy.
+    fn neighborhood_occupancy(
+        &self,
+        voxels: &DashMap<[i64; 3], Voxel>,
+        coords: [f64; 3],
+        center: f64,
+    ) -> f64 {
+        let index = voxel_index(coords);
+        self.config.neighborhood.occupancy(coords, |neighbor| {
+            if neighbor == index {
+                return center;
+            }
+            match voxels.get(&neighbor) {
+                Some(entry) => {
+                    let Voxel { count, bits, .. } = entry.value();
+                    self.occupancy(count.load(Relaxed), bits.load(Relaxed))
+                }
+                None => 0.0,
+            }
+        })
+    }
+
+    /// Computes the occupancy of a voxel, which is compared with the
+    /// threshold.
+    fn occupancy(&self, count: u64, bits: u64) -> f64 {
+        match self.config.model {
+            Model::Cumulative | Model::SlidingWindow { .. } | Model::PeriodicReset { .. } => {
+                (count + bits.count_ones() as u64) as f64
+            }
+            Model::ExponentialDecay { .. } => f64::from_bits(count) + (bits != 0) as u8 as f64,
+        }
+    }
+
+    /// Checks if the point without a voxel is reported as background.
+    /// Points with non-finite coordinates are always dropped.
+    fn is_dropped(&self, point: &Point3<f64>) -> bool {
+        self.config.out_of_range != OutOfRange::Keep || !point.iter().all(|v| v.is_finite())
+    }
+
+    /// Computes the position of the point in voxel units from the
+    /// lower corner of the range. It returns `None` if the point is
+    /// out of range, unless the range is ignored, or if the point is
+    /// not finite.
+    fn voxel_coords(&self, point: &Point3<f64>) -> Option<[f64; 3]> {
         let Config {
             range:
                 config::Range {
@@ -124,34 +479,148 @@ impl StaticPointFilter {
                     y_size,
                     z_size,
                 },
+            out_of_range,
             ..
         } = self.config;
-        let Inner {
-            ref voxels,
-            mask,
-            threshold,
-            ..
-        } = *self.inner.read().unwrap();
 
         let [px, py, pz]: [f64; 3] = (*point).into();
 
-        if !(x_bound.contains(&r64(px)) && y_bound.contains(&r64(py)) && z_bound.contains(&r64(pz)))
+        if !(px.is_finite() && py.is_finite() && pz.is_finite()) {
+            return None;
+        }
+
+        if out_of_range != OutOfRange::Ignore
+            && !(x_bound.contains(&r64(px))
+                && y_bound.contains(&r64(py))
+                && z_bound.contains(&r64(pz)))
         {
-            return true;
+            return None;
         }
 
-        let index = {
-            let x_idx = ((px - x_bound.start().raw()) / x_size.raw()).floor() as usize;
-            let y_idx = ((py - y_bound.start().raw()) / y_size.raw()).floor() as usize;
-            let z_idx = ((pz - z_bound.start().raw()) / z_size.raw()).floor() as usize;
-            [x_idx, y_idx, z_idx]
-        };
+        let [sx, sy, sz] = self.shift.map(|shift| shift as f64);
+        let coords = [
+            (px - x_bound.start().raw()) / x_size.raw() + sx,
+            (py - y_bound.start().raw()) / y_size.raw() + sy,
+            (pz - z_bound.start().raw()) / z_size.raw() + sz,
+        ];
 
-        let entry = voxels.entry(index).or_insert_with(Voxel::default);
-        let Voxel { count, bits } = entry.value();
-        let bits = bits.fetch_or(mask, Relaxed);
-        let total_count = count.load(Relaxed) + bits.count_ones() as u64;
+        Some(coords)
+    }
+}
+
+/// Voxels with decayed scores below this value are forgotten.
+const MIN_SCORE: f64 = 1e-3;
+
+/// The weight decay per frame for the half-life in frames.
+fn decay(half_life: f64) -> f64 {
+    0.5f64.powf(1.0 / half_life)
+}
+
+/// The index of the voxel containing the position in voxel units.
+fn voxel_index(coords: [f64; 3]) -> [i64; 3] {
+    coords.map(|coord| coord.floor() as i64)
+}
+
+/// Moves the bits to the counts once the bits of 64 frames are used
+/// up, or advances the mask to the next frame.
+fn accumulate(voxels: &mut DashMap<[i64; 3], Voxel>, step: usize, mask: &mut u64) {
+    if step.is_multiple_of(u64::BITS as usize) {
+        voxels.iter_mut().for_each(|mut entry| {
+            let Voxel { count, bits, .. } = entry.value_mut();
+            *count.get_mut() += std::mem::take(bits.get_mut()).count_ones() as u64;
+        });
+        *mask = 1;
+    } else {
+        *mask <<= 1;
+    }
+}
+
+/// Steps a sliding window longer than 64 frames. The bits of the
+/// last 64 frames are moved to the history ring, and the frame leaving
+/// the window is cleared from the history. Voxels which become empty
+/// are forgotten unless they keep hysteresis states.
+fn slide(voxels: &mut DashMap<[i64; 3], Voxel>, step: usize, mask: &mut u64, window: NonZeroUsize) {
+    const BITS: usize = u64::BITS as usize;
+    let words = history_words(&Model::SlidingWindow { window });
+    let finished = step.is_multiple_of(BITS);
+    let expired = step.checked_sub(window.get());
+
+    voxels.retain(|_, voxel| {
+        let Voxel {
+            bits,
+            count,
+            history,
+            ..
+        } = voxel;
+        let (bits, count) = (bits.get_mut(), count.get_mut());
+
+        if let Some(frame) = expired {
+            if let Some(word) = history.get_mut(frame / BITS % words) {
+                let bit = 1 << (frame % BITS);
+                if *word & bit != 0 {
+                    *word &= !bit;
+                    *count -= 1;
+                }
+            }
+        }
 
-        total_count >= threshold
+        if finished && *bits != 0 {
+            if history.is_empty() {
+                *history = vec![0; words].into_boxed_slice();
+            }
+            // The frames in the slot have expired, so it is normally
+            // empty already.
+            let word = &mut history[(step / BITS - 1) % words];
+            *count -= word.count_ones() as u64;
+            *count += bits.count_ones() as u64;
+            *word = std::mem::take(bits);
+        }
+
+        *count != 0 || *bits != 0 || !voxel.is_idle()
+    });
+
+    if finished {
+        *mask = 1;
+    } else {
+        *mask <<= 1;
+    }
+}
+
+/// The number of history words per voxel, which is nonzero only for
+/// sliding windows longer than 64 frames.
+fn history_words(model: &Model) -> usize {
+    match *model {
+        Model::SlidingWindow { window } if window.get() > u64::BITS as usize => {
+            window.get().div_ceil(u64::BITS as usize)
+        }
+        _ => 0,
+    }
+}
+
+/// Computes the occupancy threshold of background voxels after the
+/// steps.
+fn threshold(config: &Config, step: usize) -> f64 {
+    threshold_at(config, step, config.background_threshold.raw())
+}
+
+/// Computes the occupancy threshold for the ratio of frames.
+fn threshold_at(config: &Config, step: usize, ratio: f64) -> f64 {
+    let threshold = frames(config, step) * ratio;
+    match config.model {
+        Model::ExponentialDecay { .. } => threshold,
+        _ => threshold.ceil(),
+    }
+}
+
+/// The number of frames, or the total weight of frames for the
+/// exponential decay model, which the occupancy is counted over.
+fn frames(config: &Config, step: usize) -> f64 {
+    match config.model {
+        Model::Cumulative | Model::PeriodicReset { .. } => step as f64,
+        Model::SlidingWindow { window } => step.min(window.get()) as f64,
+        Model::ExponentialDecay { half_life } => {
+            let decay = decay(half_life.raw());
+            (1.0 - decay.powi(step.min(i32::MAX as usize) as i32)) / (1.0 - decay)
+        }
     }
 }
@@ -0,0 +1,90 @@
+use crate::{hysteresis::VoxelState, voxel_index, Inner, Mode, StaticPointFilter, Voxel};
+use nalgebra::Point3;
+use std::{
+    collections::HashMap,
+    sync::{atomic::Ordering::*, Arc},
+};
+
+/// The voxel occupancy taken when the filter is frozen. It is never
+/// modified, so lookups need no locks. With the hysteresis, it keeps
+/// 1 for background and 0 for foreground voxels instead.
+#[derive(Debug)]
+pub(crate) struct Frozen {
+    occupancy: HashMap<[i64; 3], f64>,
+    threshold: f64,
+}
+
+impl StaticPointFilter {
+    /// The current operating mode.
+    pub fn mode(&self) -> Mode {
+        if self.frozen.load().is_some() {
+            Mode::Frozen
+        } else if self.learning.load(Acquire) {
+            Mode::Learning
+        } else {
+            Mode::Adaptive
+        }
+    }
+
+    /// Switches the operating mode. Freezing takes the background
+    /// voxels learned so far, which are looked up without locks
+    /// until the filter leaves the frozen mode.
+    pub fn set_mode(&self, mode: Mode) {
+        // Hold the lock so that the statistics are not stepped
+        // meanwhile.
+        let inner = self.inner.read().unwrap();
+        let frozen = (mode == Mode::Frozen).then(|| Arc::new(self.freeze(&inner)));
+        self.frozen.store(frozen);
+        self.learning.store(mode == Mode::Learning, Release);
+    }
+
+    fn freeze(&self, inner: &Inner) -> Frozen {
+        let occupancy = inner
+            .voxels
+            .iter()
+            .map(|entry| {
+                let Voxel {
+                    count, bits, state, ..
+                } = entry.value();
+                let occupancy = if self.config.hysteresis.is_some() {
+                    let state = VoxelState::unpack(state.load(Acquire));
+                    (!state.foreground) as u8 as f64
+                } else {
+                    self.occupancy(count.load(Acquire), bits.load(Acquire))
+                };
+                (*entry.key(), occupancy)
+            })
+            .collect();
+
+        let threshold = if self.config.hysteresis.is_some() {
+            1.0
+        } else {
+            inner.threshold
+        };
+        Frozen {
+            occupancy,
+            threshold,
+        }
+    }
+
+    /// Checks the point against the frozen background if the filter
+    /// is frozen.
+    pub(crate) fn check_frozen(&self, point: &Point3<f64>) -> Option<bool> {
+        let frozen = self.frozen.load();
+        let frozen = frozen.as_ref()?;
+
+        let Some(coords) = self.voxel_coords(point) else {
+            return Some(self.is_dropped(point));
+        };
+        let occupancy = if self.config.hysteresis.is_some() {
+            // Voxels never observed start as background.
+            let index = voxel_index(coords);
+            frozen.occupancy.get(&index).copied().unwrap_or(1.0)
+        } else {
+            self.config.neighborhood.occupancy(coords, |index| {
+                frozen.occupancy.get(&index).copied().unwrap_or(0.0)
+            })
+        };
+        Some(occupancy >= frozen.threshold)
+    }
+}
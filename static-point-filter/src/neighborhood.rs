@@ -0,0 +1,94 @@
+use crate::{Aggregation, Neighborhood};
+
+impl Neighborhood {
+    /// Computes the occupancy around the point at the voxel
+    /// coordinates, which is the position in voxel units from the
+    /// lower corner of the range. `occupancy` looks up the occupancy
+    /// of voxels by indices.
+    pub(crate) fn occupancy<F>(&self, coords: [f64; 3], occupancy: F) -> f64
+    where
+        F: Fn([i64; 3]) -> f64,
+    {
+        let index = coords.map(|coord| coord.floor() as i64);
+
+        match *self {
+            Neighborhood::Single => occupancy(index),
+            Neighborhood::Adjacent {
+                connectivity,
+                aggregation,
+                axis_weights,
+            } => {
+                let center = occupancy(index);
+                let mut max = center;
+                let mut sum = center;
+                let mut total_weight = 1.0;
+
+                for offset in offsets(connectivity) {
+                    let weight: f64 = (0..3)
+                        .filter(|&axis| offset[axis] != 0)
+                        .map(|axis| axis_weights[axis].raw())
+                        .product();
+                    if weight == 0.0 {
+                        continue;
+                    }
+
+                    let neighbor = std::array::from_fn(|axis| index[axis] + offset[axis]);
+                    let value = weight * occupancy(neighbor);
+                    max = max.max(value);
+                    sum += value;
+                    total_weight += weight;
+                }
+
+                match aggregation {
+                    Aggregation::Max => max,
+                    Aggregation::WeightedSum => sum / total_weight,
+                }
+            }
+            Neighborhood::Trilinear { axes } => {
+                // Find the voxel centers below the point and the
+                // fractions to the next centers along enabled axes.
+                let mut base = index;
+                let mut fract = [0.0; 3];
+                for axis in (0..3).filter(|&axis| axes[axis]) {
+                    let coord = coords[axis] - 0.5;
+                    base[axis] = coord.floor() as i64;
+                    fract[axis] = coord - coord.floor();
+                }
+
+                (0..8)
+                    .map(|corner: usize| {
+                        let mut neighbor = base;
+                        let mut weight = 1.0;
+                        for axis in 0..3 {
+                            if corner >> axis & 1 == 1 {
+                                neighbor[axis] += 1;
+                                weight *= fract[axis];
+                            } else {
+                                weight *= 1.0 - fract[axis];
+                            }
+                        }
+                        (neighbor, weight)
+                    })
+                    .filter(|&(_, weight)| weight > 0.0)
+                    .map(|(neighbor, weight)| weight * occupancy(neighbor))
+                    .sum()
+            }
+        }
+    }
+}
+
+/// The offsets to adjacent voxels sharing faces, edges or corners.
+fn offsets(connectivity: u8) -> impl Iterator<Item = [i64; 3]> {
+    let max_axes = match connectivity {
+        6 => 1,
+        18 => 2,
+        _ => 3,
+    };
+
+    (0..27)
+        .map(|code: i64| [code % 3 - 1, code / 3 % 3 - 1, code / 9 - 1])
+        .filter(move |offset| {
+            let axes = offset.iter().filter(|&&delta| delta != 0).count();
+            (1..=max_axes).contains(&axes)
+        })
+}
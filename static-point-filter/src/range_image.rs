@@ -0,0 +1,324 @@
+//! The background model over the range image of a spinning LiDAR.
+//!
+//! Points are binned by their elevation and azimuth angles in the
+//! sensor frame, so that each cell roughly corresponds to a beam
+//! direction. Each cell learns a histogram of return ranges, and
+//! range bins which are occupied in at least `background_threshold`
+//! of the frames are background surfaces. A point is foreground if it
+//! is closer than the nearest background surface of its cell by more
+//! than the margin.
+
+use dashmap::DashMap;
+use nalgebra::{Isometry3, Point3, Translation3, UnitQuaternion};
+use noisy_float::prelude::*;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{ops::RangeInclusive, sync::RwLock};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "with-schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "with-schemars", schemars(rename = "RangeImageFilterConfig"))]
+pub struct RangeImageConfig {
+    /// The sensor pose in the point cloud frame.
+    pub sensor_pose: SensorPose,
+
+    /// The width of azimuth bins in degrees.
+    #[cfg_attr(feature = "with-schemars", schemars(with = "f64"))]
+    pub azimuth_resolution_degrees: R64,
+
+    /// The height of elevation bins in degrees.
+    #[cfg_attr(feature = "with-schemars", schemars(with = "f64"))]
+    pub elevation_resolution_degrees: R64,
+
+    /// The elevation angles in degrees covered by the beams. Points
+    /// out of the bound are reported as background.
+    #[serde(with = "crate::utils::serde_bound")]
+    #[cfg_attr(feature = "with-schemars", schemars(with = "[f64; 2]"))]
+    pub elevation_bound: RangeInclusive<R64>,
+
+    /// The width of range bins in meters.
+    #[cfg_attr(feature = "with-schemars", schemars(with = "f64"))]
+    pub range_resolution: R64,
+
+    /// The distance in meters by which foreground points must be
+    /// closer than the background surface.
+    #[cfg_attr(feature = "with-schemars", schemars(with = "f64"))]
+    pub margin: R64,
+
+    /// The ratio of frames where a range bin is occupied to be a
+    /// background surface.
+    #[cfg_attr(feature = "with-schemars", schemars(with = "f64"))]
+    pub background_threshold: R64,
+}
+
+impl RangeImageConfig {
+    pub fn build(&self) -> RangeImageFilter {
+        RangeImageFilter::new(self)
+    }
+}
+
+/// The sensor position in meters and orientation in roll, pitch and
+/// yaw degrees.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "with-schemars", derive(schemars::JsonSchema))]
+pub struct SensorPose {
+    #[cfg_attr(feature = "with-schemars", schemars(with = "[f64; 3]"))]
+    pub position: [R64; 3],
+    #[serde(default = "zeros")]
+    #[cfg_attr(feature = "with-schemars", schemars(with = "[f64; 3]"))]
+    pub rotation_degrees: [R64; 3],
+}
+
+impl SensorPose {
+    fn to_isometry(&self) -> Isometry3<f64> {
+        let [x, y, z] = self.position.map(|v| v.raw());
+        let [roll, pitch, yaw] = self.rotation_degrees.map(|v| v.raw().to_radians());
+        Isometry3::from_parts(
+            Translation3::new(x, y, z),
+            UnitQuaternion::from_euler_angles(roll, pitch, yaw),
+        )
+    }
+}
+
+fn zeros() -> [R64; 3] {
+    [r64(0.0); 3]
+}
+
+#[derive(Debug)]
+pub struct RangeImageFilter {
+    config: RangeImageConfig,
+    pose: Isometry3<f64>,
+    inner: RwLock<Inner>,
+}
+
+impl Clone for RangeImageFilter {
+    fn clone(&self) -> Self {
+        let inner = self.inner.read().unwrap();
+        Self {
+            config: self.config.clone(),
+            pose: self.pose,
+            inner: RwLock::new(inner.clone()),
+        }
+    }
+}
+
+impl Serialize for RangeImageFilter {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.config.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for RangeImageFilter {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let config = RangeImageConfig::deserialize(deserializer)?;
+        Ok(Self::new(&config))
+    }
+}
+
+#[cfg(feature = "with-schemars")]
+impl schemars::JsonSchema for RangeImageFilter {
+    fn schema_name() -> String {
+        RangeImageConfig::schema_name()
+    }
+
+    fn json_schema(gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        RangeImageConfig::json_schema(gen)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Inner {
+    /// The cells indexed by elevation and azimuth bins.
+    cells: DashMap<[i64; 2], Cell>,
+    step: usize,
+    mask: u64,
+    threshold: u64,
+}
+
+/// The range histogram of a cell, sorted by range bins.
+#[derive(Debug, Clone, Default)]
+struct Cell {
+    bins: Vec<RangeBin>,
+}
+
+/// The occupancy of a range bin, which is counted in the same way as
+/// voxels.
+#[derive(Debug, Clone)]
+struct RangeBin {
+    index: u32,
+    bits: u64,
+    count: u64,
+}
+
+impl RangeBin {
+    fn occupancy(&self, mask: u64) -> u64 {
+        self.count + (self.bits | mask).count_ones() as u64
+    }
+}
+
+impl Cell {
+    /// Checks if the range bin is background, or it is not in front
+    /// of the nearest background bin by more than the margin. The
+    /// range bin is counted as occupied in the current frame.
+    fn is_background(&self, index: u32, margin: u32, mask: u64, threshold: u64) -> bool {
+        let occupancy = match self.bins.iter().find(|bin| bin.index == index) {
+            Some(bin) => bin.occupancy(mask),
+            None => 1,
+        };
+        if occupancy >= threshold {
+            return true;
+        }
+
+        // Bins are sorted, so the first background bin is the
+        // nearest surface.
+        self.bins
+            .iter()
+            .find(|bin| bin.occupancy(0) >= threshold)
+            .is_some_and(|bin| bin.index <= index.saturating_add(margin))
+    }
+}
+
+impl RangeImageFilter {
+    pub fn new(config: &RangeImageConfig) -> Self {
+        Self {
+            config: config.clone(),
+            pose: config.sensor_pose.to_isometry(),
+            inner: RwLock::new(Inner {
+                cells: DashMap::new(),
+                step: 0,
+                mask: 1,
+                threshold: 0,
+            }),
+        }
+    }
+
+    pub fn config(&self) -> &RangeImageConfig {
+        &self.config
+    }
+
+    pub fn step(&self) {
+        let background_threshold = self.config.background_threshold.raw();
+        let Inner {
+            cells,
+            step,
+            mask,
+            threshold,
+        } = &mut *self.inner.write().unwrap();
+
+        *step += 1;
+        *threshold = (*step as f64 * background_threshold).ceil() as u64;
+
+        if step.is_multiple_of(u64::BITS as usize) {
+            cells.iter_mut().for_each(|mut entry| {
+                for bin in &mut entry.value_mut().bins {
+                    bin.count += std::mem::take(&mut bin.bits).count_ones() as u64;
+                }
+            });
+            *mask = 1;
+        } else {
+            *mask <<= 1;
+        }
+    }
+
+    /// Checks if the point lies on or behind a background surface,
+    /// and records the return range in the current frame.
+    pub fn check_is_background(&self, point: &Point3<f64>) -> bool {
+        let Inner {
+            ref cells,
+            mask,
+            threshold,
+            ..
+        } = *self.inner.read().unwrap();
+
+        let Some((cell_index, bin_index)) = self.bin_index(point) else {
+            return true;
+        };
+
+        let mut cell = cells.entry(cell_index).or_default();
+        let bins = &mut cell.bins;
+        let pos = match bins.binary_search_by_key(&bin_index, |bin| bin.index) {
+            Ok(pos) => pos,
+            Err(pos) => {
+                let bin = RangeBin {
+                    index: bin_index,
+                    bits: 0,
+                    count: 0,
+                };
+                bins.insert(pos, bin);
+                pos
+            }
+        };
+        bins[pos].bits |= mask;
+
+        cell.is_background(bin_index, self.margin_bins(), mask, threshold)
+    }
+
+    /// Checks if the point would be classified as background by
+    /// [check_is_background](Self::check_is_background) without
+    /// updating the statistics.
+    pub fn is_background(&self, point: &Point3<f64>) -> bool {
+        let Inner {
+            ref cells,
+            mask,
+            threshold,
+            ..
+        } = *self.inner.read().unwrap();
+
+        let Some((cell_index, bin_index)) = self.bin_index(point) else {
+            return true;
+        };
+
+        let is_background = match cells.get(&cell_index) {
+            Some(cell) => cell.is_background(bin_index, self.margin_bins(), mask, threshold),
+            None => threshold <= 1,
+        };
+        is_background
+    }
+
+    /// The margin in whole range bins.
+    fn margin_bins(&self) -> u32 {
+        (self.config.margin / self.config.range_resolution)
+            .raw()
+            .floor() as u32
+    }
+
+    /// Computes the cell and the range bin of the point, or returns
+    /// `None` if the point is out of the elevation bound or at the
+    /// sensor origin.
+    fn bin_index(&self, point: &Point3<f64>) -> Option<([i64; 2], u32)> {
+        let RangeImageConfig {
+            azimuth_resolution_degrees,
+            elevation_resolution_degrees,
+            ref elevation_bound,
+            range_resolution,
+            ..
+        } = self.config;
+
+        let local = self.pose.inverse_transform_point(point);
+        let range = local.coords.norm();
+        if !range.is_finite() || range == 0.0 {
+            return None;
+        }
+
+        let elevation = local.z.atan2(local.x.hypot(local.y)).to_degrees();
+        if !elevation_bound.contains(&r64(elevation)) {
+            return None;
+        }
+        let azimuth = local.y.atan2(local.x).to_degrees() + 180.0;
+
+        let n_azimuth = (360.0 / azimuth_resolution_degrees.raw()).ceil() as i64;
+        let azimuth_index =
+            ((azimuth / azimuth_resolution_degrees.raw()).floor() as i64).rem_euclid(n_azimuth);
+        let elevation_index = ((elevation - elevation_bound.start().raw())
+            / elevation_resolution_degrees.raw())
+        .floor() as i64;
+        let bin_index = (range / range_resolution.raw()).floor() as u32;
+
+        Some(([elevation_index, azimuth_index], bin_index))
+    }
+}
@@ -0,0 +1,301 @@
+//! The binary snapshot of learned background statistics.
+//!
+//! All values are little-endian.
+//!
+//! | Field      | Type         | Description                          |
+//! |------------|--------------|--------------------------------------|
+//! | magic      | `[u8; 4]`    | `b"SPFS"`                            |
+//! | version    | `u32`        | The format version, currently 5      |
+//! | grid       | `[f64; 9]`   | The x, y, z bounds and voxel sizes   |
+//! | model      | `u32`, `f64` | The model kind and its parameter     |
+//! | hysteresis | `[f64; 5]`   | The hysteresis config, or zeros      |
+//! | step       | `u64`        | The number of frames stepped         |
+//! | mask       | `u64`        | The bit of the current frame         |
+//! | len        | `u64`        | The number of voxels                 |
+//! | voxels     | `len` times  | `[i64; 3]` index, `u64` bits, `u64` count, `u64` state |
+//! | checksum   | `u32`        | The CRC-32 of all preceding bytes    |
+//!
+//! For sliding windows longer than 64 frames, each voxel is followed
+//! by its `ceil(window / 64)` history words as `u64`. They are zeros
+//! if the voxel has no history yet.
+//!
+//! The model kinds are 0 for cumulative, 1 for sliding window, 2 for
+//! exponential decay and 3 for periodic reset. The hysteresis config
+//! is the promote threshold and frames, the demote threshold and
+//! frames, and the persistence frames. The state is the packed
+//! hysteresis state of the voxel.
+//!
+//! Versions 1 to 4 have no hysteresis field and voxel states, and
+//! imply no hysteresis. Versions 1 and 2 additionally have no model
+//! field and imply the cumulative model. Versions 2 and 3 store
+//! `[i32; 3]` indices, and version 1 unsigned `[u32; 3]` indices. All
+//! of them are still readable.
+
+use crate::{config, history_words, Config, Hysteresis, Inner, Model, StaticPointFilter, Voxel};
+use anyhow::{bail, ensure, Result};
+use dashmap::DashMap;
+use std::{
+    io::{Read, Write},
+    sync::{atomic::AtomicU64, atomic::Ordering::*},
+};
+
+const MAGIC: &[u8; 4] = b"SPFS";
+const VERSION: u32 = 5;
+
+impl StaticPointFilter {
+    /// Writes the learned statistics to a binary snapshot.
+    pub fn save_state<W: Write>(&self, mut writer: W) -> Result<()> {
+        let inner = self.inner.read().unwrap();
+        let words = history_words(&self.config.model);
+        let mut buf = Vec::with_capacity(100 + inner.voxels.len() * (48 + words * 8));
+
+        buf.extend(MAGIC);
+        buf.extend(VERSION.to_le_bytes());
+        for value in grid(&self.config) {
+            buf.extend(value.to_le_bytes());
+        }
+        let (kind, parameter) = model(&self.config.model);
+        buf.extend(kind.to_le_bytes());
+        buf.extend(parameter.to_le_bytes());
+        for value in hysteresis(&self.config.hysteresis) {
+            buf.extend(value.to_le_bytes());
+        }
+        buf.extend((inner.step as u64).to_le_bytes());
+        buf.extend(inner.mask.to_le_bytes());
+        buf.extend((inner.voxels.len() as u64).to_le_bytes());
+
+        for entry in inner.voxels.iter() {
+            for (key, shift) in entry.key().iter().zip(self.shift) {
+                buf.extend((key - shift).to_le_bytes());
+            }
+            let Voxel {
+                bits,
+                count,
+                state,
+                history,
+                ..
+            } = entry.value();
+            buf.extend(bits.load(Acquire).to_le_bytes());
+            buf.extend(count.load(Acquire).to_le_bytes());
+            buf.extend(state.load(Acquire).to_le_bytes());
+            for index in 0..words {
+                let word = history.get(index).copied().unwrap_or(0);
+                buf.extend(word.to_le_bytes());
+            }
+        }
+
+        let checksum = crc32fast::hash(&buf);
+        buf.extend(checksum.to_le_bytes());
+
+        writer.write_all(&buf)?;
+        Ok(())
+    }
+
+    /// Replaces the statistics with a snapshot saved by
+    /// [save_state](Self::save_state). The snapshot must be recorded
+    /// with the same range, voxel size, model and hysteresis. The
+    /// background threshold may differ.
+    pub fn load_state<R: Read>(&self, reader: R) -> Result<()> {
+        let inner = read_state(&self.config, self.shift, reader)?;
+        *self.inner.write().unwrap() = inner;
+
+        // Take the frozen background from the loaded statistics.
+        self.set_mode(self.mode());
+        Ok(())
+    }
+
+    /// Starts a filter from a snapshot saved by
+    /// [save_state](Self::save_state).
+    pub fn from_state<R: Read>(config: &Config, reader: R) -> Result<Self> {
+        let inner = read_state(config, [0; 3], reader)?;
+        let filter = Self::from_inner(config, inner);
+        filter.set_mode(config.mode);
+        Ok(filter)
+    }
+}
+
+/// Reads a snapshot, adding `shift` to the voxel indices to make
+/// the keys of the voxel map.
+fn read_state<R: Read>(config: &Config, shift: [i64; 3], mut reader: R) -> Result<Inner> {
+    let mut data = vec![];
+    reader.read_to_end(&mut data)?;
+
+    ensure!(
+        data.len() >= MAGIC.len() && &data[..MAGIC.len()] == MAGIC,
+        "not a background state snapshot"
+    );
+    let Some(body_len) = data.len().checked_sub(4) else {
+        bail!("the snapshot is truncated");
+    };
+    let (body, checksum) = data.split_at(body_len);
+    ensure!(
+        crc32fast::hash(body).to_le_bytes() == checksum,
+        "the snapshot checksum does not match"
+    );
+
+    let mut cursor = Cursor(&body[MAGIC.len()..]);
+    let version = cursor.u32()?;
+    ensure!(
+        (1..=VERSION).contains(&version),
+        "unsupported snapshot version {version}, expect at most {VERSION}"
+    );
+
+    let saved: Vec<f64> = (0..9).map(|_| cursor.f64()).collect::<Result<_>>()?;
+    ensure!(
+        saved == grid(config),
+        "the snapshot was recorded with a different range or voxel size"
+    );
+
+    let saved_model = if version >= 3 {
+        (cursor.u32()?, cursor.f64()?)
+    } else {
+        model(&Model::Cumulative)
+    };
+    ensure!(
+        saved_model == model(&config.model),
+        "the snapshot was recorded with a different background model"
+    );
+
+    let saved_hysteresis: Vec<f64> = if version >= 5 {
+        (0..5).map(|_| cursor.f64()).collect::<Result<_>>()?
+    } else {
+        hysteresis(&None)
+    };
+    ensure!(
+        saved_hysteresis == hysteresis(&config.hysteresis),
+        "the snapshot was recorded with a different hysteresis"
+    );
+
+    let step = cursor.u64()? as usize;
+    let mask = cursor.u64()?;
+    let len = cursor.u64()?;
+    let words = history_words(&config.model);
+
+    let voxels = DashMap::new();
+    for _ in 0..len {
+        let index = match version {
+            1 => [cursor.u32()?, cursor.u32()?, cursor.u32()?].map(i64::from),
+            2 | 3 => [cursor.i32()?, cursor.i32()?, cursor.i32()?].map(i64::from),
+            _ => [cursor.i64()?, cursor.i64()?, cursor.i64()?],
+        };
+        let bits = cursor.u64()?;
+        let count = cursor.u64()?;
+        let state = if version >= 5 { cursor.u64()? } else { 0 };
+        let history: Vec<u64> = (0..words).map(|_| cursor.u64()).collect::<Result<_>>()?;
+        let history = if history.iter().all(|&word| word == 0) {
+            Box::default()
+        } else {
+            history.into_boxed_slice()
+        };
+        voxels.insert(
+            std::array::from_fn(|axis| index[axis] + shift[axis]),
+            Voxel {
+                bits: AtomicU64::new(bits),
+                count: AtomicU64::new(count),
+                last_seen: AtomicU64::new(step as u64),
+                state: AtomicU64::new(state),
+                history,
+            },
+        );
+    }
+    ensure!(cursor.0.is_empty(), "the snapshot has trailing bytes");
+
+    let threshold = crate::threshold(config, step);
+
+    Ok(Inner {
+        voxels,
+        step,
+        mask,
+        threshold,
+    })
+}
+
+/// The bounds and voxel sizes which determine the voxel grid.
+fn grid(config: &Config) -> Vec<f64> {
+    let Config {
+        range:
+            config::Range {
+                x_bound,
+                y_bound,
+                z_bound,
+            },
+        voxel_size:
+            config::VoxelSize {
+                x_size,
+                y_size,
+                z_size,
+            },
+        ..
+    } = config;
+
+    [x_bound, y_bound, z_bound]
+        .into_iter()
+        .flat_map(|bound| [*bound.start(), *bound.end()])
+        .chain([*x_size, *y_size, *z_size])
+        .map(|value| value.raw())
+        .collect()
+}
+
+/// The kind and the parameter of the model.
+fn model(model: &Model) -> (u32, f64) {
+    match *model {
+        Model::Cumulative => (0, 0.0),
+        Model::SlidingWindow { window } => (1, window.get() as f64),
+        Model::ExponentialDecay { half_life } => (2, half_life.raw()),
+        Model::PeriodicReset { period } => (3, period.get() as f64),
+    }
+}
+
+/// The hysteresis parameters, which are all zeros without the
+/// hysteresis.
+fn hysteresis(hysteresis: &Option<Hysteresis>) -> Vec<f64> {
+    let Some(hysteresis) = hysteresis else {
+        return vec![0.0; 5];
+    };
+    let Hysteresis {
+        promote_threshold,
+        promote_frames,
+        demote_threshold,
+        demote_frames,
+        persistence_frames,
+    } = *hysteresis;
+
+    vec![
+        promote_threshold.raw(),
+        promote_frames.get() as f64,
+        demote_threshold.raw(),
+        demote_frames.get() as f64,
+        persistence_frames as f64,
+    ]
+}
+
+struct Cursor<'a>(&'a [u8]);
+
+impl Cursor<'_> {
+    fn take<const N: usize>(&mut self) -> Result<[u8; N]> {
+        ensure!(self.0.len() >= N, "the snapshot is truncated");
+        let (head, tail) = self.0.split_at(N);
+        self.0 = tail;
+        Ok(head.try_into().unwrap())
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.take()?))
+    }
+
+    fn i32(&mut self) -> Result<i32> {
+        Ok(i32::from_le_bytes(self.take()?))
+    }
+
+    fn i64(&mut self) -> Result<i64> {
+        Ok(i64::from_le_bytes(self.take()?))
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.take()?))
+    }
+
+    fn f64(&mut self) -> Result<f64> {
+        Ok(f64::from_le_bytes(self.take()?))
+    }
+}
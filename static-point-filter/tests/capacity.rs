@@ -0,0 +1,152 @@
+mod common;
+
+use nalgebra::Point3;
+use noisy_float::prelude::*;
+use static_point_filter::{Aggregation, Eviction, Neighborhood};
+use std::num::NonZeroUsize;
+
+/// Points in a row of voxels along the x axis.
+fn row(xs: impl IntoIterator<Item = i32>) -> Vec<Point3<f64>> {
+    xs.into_iter()
+        .map(|x| Point3::new(x as f64, 0.0, 0.0))
+        .collect()
+}
+
+#[test]
+fn voxel_cap_test() {
+    let filter = common::load(|config| {
+        config.max_voxels = NonZeroUsize::new(10);
+        config.eviction = Eviction::LeastRecentlyObserved;
+    });
+    let walls = row(-4..=0);
+
+    // Learn the walls, and then observe passing objects which fill
+    // up the map.
+    for _ in 0..10 {
+        walls.iter().for_each(|p| {
+            filter.check_is_background(p);
+        });
+        filter.step();
+    }
+    for x in 1..=4 {
+        for y in -4..=4 {
+            filter.check_is_background(&Point3::new(x as f64, y as f64, 0.0));
+        }
+        walls.iter().for_each(|p| {
+            filter.check_is_background(p);
+        });
+        filter.step();
+
+        let stats = filter.memory_stats();
+        assert!(stats.voxels <= 10);
+    }
+
+    // Background voxels are never evicted.
+    assert!(walls.iter().all(|p| filter.is_background(p)));
+
+    let stats = filter.memory_stats();
+    assert!(stats.evicted > 0);
+    assert!(stats.refused > 0);
+    assert_eq!(stats.max_voxels, Some(10));
+}
+
+#[test]
+fn eviction_order_test() {
+    let often = Point3::new(-2.0, 0.0, 0.0);
+    let recent = row([1, 2]);
+
+    let run = |eviction| {
+        let filter = common::load(|config| {
+            config.max_voxels = NonZeroUsize::new(3);
+            config.eviction = eviction;
+        });
+        for step in 0..8 {
+            if step < 4 {
+                filter.check_is_background(&often);
+            }
+            if step == 7 {
+                recent.iter().for_each(|p| {
+                    filter.check_is_background(p);
+                });
+            }
+            filter.step();
+        }
+        assert_eq!(filter.memory_stats().evicted, 1);
+
+        // List all remaining voxels.
+        let centers: Vec<_> = filter
+            .export_background(0.0)
+            .into_iter()
+            .map(|voxel| voxel.center)
+            .collect();
+        centers.contains(&often)
+    };
+
+    assert!(!run(Eviction::LeastRecentlyObserved));
+    assert!(run(Eviction::LowestOccupancy));
+}
+
+#[test]
+fn neighborhood_eviction_test() {
+    // The wall is observed in all frames, and its neighbor in most of
+    // them. Points between them are background by interpolation,
+    // though the neighbor is not background alone.
+    let wall = Point3::new(0.0, 0.0, 0.0);
+    let neighbor = Point3::new(1.0, 0.0, 0.0);
+    let edge = Point3::new(0.5, 0.0, 0.0);
+    let passing = row([-4, -3]);
+
+    let run = |max_voxels| {
+        let filter = common::load(|config| {
+            config.max_voxels = NonZeroUsize::new(max_voxels);
+            config.neighborhood = Neighborhood::Trilinear { axes: [true; 3] };
+        });
+        for step in 0..20 {
+            filter.check_is_background(&wall);
+            if step < 14 {
+                filter.check_is_background(&neighbor);
+            }
+            if step == 19 {
+                passing.iter().for_each(|p| {
+                    filter.check_is_background(p);
+                });
+            }
+            filter.step();
+        }
+        filter
+    };
+
+    // The neighbor is observed least recently, but it is kept since
+    // the edge depends on it.
+    let filter = run(4);
+    assert_eq!(filter.memory_stats().evicted, 1);
+    assert!(filter.is_background(&edge));
+    assert!(!filter.is_background(&neighbor));
+    assert_eq!(filter.is_background(&edge), run(100).is_background(&edge));
+}
+
+#[test]
+fn refused_neighborhood_test() {
+    let filter = common::load(|config| {
+        config.max_voxels = NonZeroUsize::new(2);
+        config.neighborhood = Neighborhood::Adjacent {
+            connectivity: 6,
+            aggregation: Aggregation::Max,
+            axis_weights: [r64(1.0); 3],
+        };
+    });
+    let walls = row([-4, 0]);
+    for _ in 0..10 {
+        walls.iter().for_each(|p| {
+            filter.check_is_background(p);
+        });
+        filter.step();
+    }
+
+    // The new voxel is refused, and classified by its neighborhood as
+    // if it were empty.
+    let point = Point3::new(1.0, 0.0, 0.0);
+    assert!(filter.is_background(&point));
+    assert!(filter.check_is_background(&point));
+    assert_eq!(filter.memory_stats().refused, 1);
+}
@@ -0,0 +1,11 @@
+use static_point_filter::{Config, StaticPointFilter};
+use std::fs;
+
+/// Builds a filter from `tests/simple.json` with the config modified
+/// by the closure.
+pub fn load(modify: impl FnOnce(&mut Config)) -> StaticPointFilter {
+    let text = fs::read_to_string("tests/simple.json").unwrap();
+    let mut config: Config = serde_json::from_str(&text).unwrap();
+    modify(&mut config);
+    config.build()
+}
@@ -0,0 +1,32 @@
+use nalgebra::Point3;
+use static_point_filter::StaticPointFilter;
+use std::fs;
+
+#[test]
+fn export_background_test() {
+    let text = fs::read_to_string("tests/simple.json").unwrap();
+    let filter: StaticPointFilter = serde_json::from_str(&text).unwrap();
+    let wall = Point3::new(2.2, 0.1, -0.3);
+    let flicker = Point3::new(-2.0, 0.0, 0.0);
+
+    for step in 0..10 {
+        filter.check_is_background(&wall);
+        if step % 2 == 0 {
+            filter.check_is_background(&flicker);
+        }
+        filter.step();
+    }
+
+    let threshold = filter.config().background_threshold.raw();
+    let background = filter.export_background(threshold);
+    assert_eq!(background.len(), 1);
+    assert_eq!(background[0].center, Point3::new(2.0, 0.0, 0.0));
+    assert_eq!(background[0].occupancy, 1.0);
+    assert!(filter.is_background(&wall));
+
+    // A lower threshold includes the flickering voxel.
+    let background = filter.export_background(0.5);
+    assert_eq!(background.len(), 2);
+    assert_eq!(background[0].center, Point3::new(-2.0, 0.0, 0.0));
+    assert_eq!(background[0].occupancy, 0.5);
+}
@@ -0,0 +1,204 @@
+mod common;
+
+use nalgebra::Point3;
+use noisy_float::prelude::*;
+use static_point_filter::{Config, Hysteresis, Mode, Model, StaticPointFilter};
+use std::num::{NonZeroU32, NonZeroUsize};
+
+fn hysteresis(persistence_frames: u32) -> Hysteresis {
+    Hysteresis {
+        promote_threshold: r64(0.8),
+        promote_frames: NonZeroU32::new(3).unwrap(),
+        demote_threshold: r64(0.5),
+        demote_frames: NonZeroU32::new(2).unwrap(),
+        persistence_frames,
+    }
+}
+
+#[test]
+fn noise_test() {
+    let wall = Point3::new(0.0, 0.0, 0.0);
+    let speck = Point3::new(-2.0, 0.0, 0.0);
+    let car = Point3::new(2.0, 0.0, 0.0);
+
+    // Returns whether the speck is always background, and the frames
+    // where the car is foreground.
+    let run = |filter: &StaticPointFilter| {
+        let mut speck_is_background = true;
+        let mut car_frames = vec![];
+        for frame in 0..50 {
+            filter.check_is_background(&wall);
+            if frame % 10 == 5 {
+                speck_is_background &= filter.check_is_background(&speck);
+            }
+            if (40..45).contains(&frame) && !filter.check_is_background(&car) {
+                car_frames.push(frame);
+            }
+            filter.step();
+        }
+        (speck_is_background, car_frames)
+    };
+
+    let filter = common::load(|_| ());
+    assert_eq!(run(&filter), (false, vec![40, 41, 42, 43, 44]));
+
+    // The car is foreground from the second frame.
+    let filter = common::load(|config| config.hysteresis = Some(hysteresis(0)));
+    assert_eq!(run(&filter), (true, vec![41, 42, 43, 44]));
+}
+
+#[test]
+fn persistence_test() {
+    let wall = Point3::new(0.0, 0.0, 0.0);
+    let pedestrian = Point3::new(2.0, 0.0, 0.0);
+    let model = Model::SlidingWindow {
+        window: NonZeroUsize::new(10).unwrap(),
+    };
+
+    // Returns the first frame where the pedestrian standing since
+    // frame 20 is absorbed into the background, once it has been
+    // foreground.
+    let absorbed = |filter: &StaticPointFilter| {
+        let mut foreground = false;
+        (0..100).find(|&frame| {
+            filter.check_is_background(&wall);
+            let is_background = frame >= 20 && filter.check_is_background(&pedestrian);
+            filter.step();
+            foreground |= frame >= 20 && !is_background;
+            foreground && is_background
+        })
+    };
+
+    let cases = [
+        (None, 28),
+        (Some(hysteresis(0)), 29),
+        (Some(hysteresis(30)), 53),
+    ];
+    for (settings, frame) in cases {
+        let filter = common::load(|config| {
+            config.model = model;
+            config.hysteresis = settings;
+        });
+        assert_eq!(absorbed(&filter), Some(frame));
+    }
+}
+
+#[test]
+fn forgetting_model_test() {
+    let wall = Point3::new(0.0, 0.0, 0.0);
+    let car = Point3::new(2.0, 0.0, 0.0);
+
+    // Returns whether the car is foreground when it returns, after
+    // its voxel has been emptied by the model.
+    let returns_as_foreground = |filter: &StaticPointFilter| {
+        for frame in 0..40 {
+            filter.check_is_background(&wall);
+            if (20..25).contains(&frame) {
+                filter.check_is_background(&car);
+            }
+            filter.step();
+        }
+        assert!(!filter.is_background(&car));
+        !filter.check_is_background(&car)
+    };
+
+    let window = Model::SlidingWindow {
+        window: NonZeroUsize::new(10).unwrap(),
+    };
+    assert!(returns_as_foreground(&common::load(|config| {
+        config.model = window;
+        config.hysteresis = Some(hysteresis(30));
+    })));
+
+    let reset = Model::PeriodicReset {
+        period: NonZeroUsize::new(30).unwrap(),
+    };
+    assert!(returns_as_foreground(&common::load(|config| {
+        config.model = reset;
+        config.hysteresis = Some(hysteresis(30));
+    })));
+}
+
+#[test]
+fn frozen_test() {
+    let filter = common::load(|config| config.hysteresis = Some(hysteresis(30)));
+    let wall = Point3::new(0.0, 0.0, 0.0);
+    let pedestrian = Point3::new(2.0, 0.0, 0.0);
+    for frame in 0..30 {
+        filter.check_is_background(&wall);
+        if frame >= 20 {
+            filter.check_is_background(&pedestrian);
+        }
+        filter.step();
+    }
+
+    filter.set_mode(Mode::Frozen);
+    assert!(filter.check_is_background(&wall));
+    assert!(!filter.check_is_background(&pedestrian));
+    assert!(filter.check_is_background(&Point3::new(-2.0, 0.0, 0.0)));
+}
+
+#[test]
+fn state_test() {
+    let filter = common::load(|config| config.hysteresis = Some(hysteresis(30)));
+    let wall = Point3::new(0.0, 0.0, 0.0);
+    let pedestrian = Point3::new(2.0, 0.0, 0.0);
+    for frame in 0..30 {
+        filter.check_is_background(&wall);
+        if frame >= 20 {
+            filter.check_is_background(&pedestrian);
+        }
+        filter.step();
+    }
+    assert!(!filter.is_background(&pedestrian));
+
+    // The export takes the states regardless of the threshold.
+    let centers: Vec<_> = filter
+        .export_background(0.0)
+        .into_iter()
+        .map(|voxel| voxel.center)
+        .collect();
+    assert_eq!(centers, vec![wall]);
+
+    // The snapshot keeps the states.
+    let mut snapshot = vec![];
+    filter.save_state(&mut snapshot).unwrap();
+    let restored = StaticPointFilter::from_state(filter.config(), snapshot.as_slice()).unwrap();
+    assert!(restored.is_background(&wall));
+    assert!(!restored.is_background(&pedestrian));
+    assert_eq!(
+        restored.export_background(0.0),
+        filter.export_background(0.0)
+    );
+
+    // The hysteresis must match.
+    let mut config = filter.config().clone();
+    config.hysteresis = Some(hysteresis(0));
+    assert!(StaticPointFilter::from_state(&config, snapshot.as_slice()).is_err());
+    config.hysteresis = None;
+    assert!(StaticPointFilter::from_state(&config, snapshot.as_slice()).is_err());
+}
+
+#[test]
+fn config_test() {
+    let text = r#"{
+        "range": {
+            "x_bound": [-4.5, 4.5],
+            "y_bound": [-4.5, 4.5],
+            "z_bound": [-4.5, 4.5]
+        },
+        "voxel_size": { "x_size": 1.0, "y_size": 1.0, "z_size": 1.0 },
+        "background_threshold": 0.8,
+        "hysteresis": {
+            "promote_threshold": 0.8,
+            "promote_frames": 3,
+            "demote_threshold": 0.5,
+            "demote_frames": 0
+        }
+    }"#;
+    assert!(serde_json::from_str::<Config>(text).is_err());
+
+    let text = text.replace("\"demote_frames\": 0", "\"demote_frames\": 2");
+    let config: Config = serde_json::from_str(&text).unwrap();
+    assert_eq!(config.hysteresis, Some(hysteresis(0)));
+}
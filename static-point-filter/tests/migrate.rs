@@ -0,0 +1,108 @@
+mod common;
+
+use nalgebra::Point3;
+use noisy_float::prelude::*;
+use static_point_filter::StaticPointFilter;
+use std::{fs, num::NonZeroUsize, ops::RangeInclusive};
+
+#[test]
+fn migrate_test() {
+    let text = fs::read_to_string("tests/simple.json").unwrap();
+    let filter: StaticPointFilter = serde_json::from_str(&text).unwrap();
+    let wall = Point3::new(2.0, 0.0, 0.0);
+    let edge = Point3::new(-4.0, 0.0, 0.0);
+
+    for _ in 0..10 {
+        assert!(filter.check_is_background(&wall));
+        assert!(filter.check_is_background(&edge));
+        filter.step();
+    }
+
+    // Shift the range by whole voxels, which drops the voxel of the
+    // edge point.
+    let mut config = filter.config().clone();
+    config.range.x_bound = shift(&config.range.x_bound, 2.0);
+    let migrated = filter.migrate(&config).unwrap();
+    assert!(migrated.is_background(&wall));
+    assert!(!migrated.is_background(&Point3::new(-2.0, 0.0, 0.0)));
+
+    // Unaligned grids cannot be migrated.
+    let mut config = filter.config().clone();
+    config.range.x_bound = shift(&config.range.x_bound, 0.5);
+    assert!(filter.migrate(&config).is_none());
+}
+
+#[test]
+fn shared_statistics_test() {
+    let filter = common::load(|_| {});
+    let wall = Point3::new(2.0, 0.0, 0.0);
+
+    let mut config = filter.config().clone();
+    config.range.x_bound = shift(&config.range.x_bound, 2.0);
+    let migrated = filter.migrate(&config).unwrap();
+
+    // Points observed by the old filter after migration are kept.
+    for _ in 0..10 {
+        assert!(filter.check_is_background(&wall));
+        filter.step();
+    }
+    assert!(migrated.is_background(&wall));
+
+    // Voxels are indexed in the new range.
+    let voxels = migrated.export_background(0.8);
+    assert_eq!(voxels.len(), 1);
+    assert_eq!(voxels[0].index, [4, 4, 4]);
+
+    let mut state = vec![];
+    migrated.save_state(&mut state).unwrap();
+    let restored = StaticPointFilter::from_state(&config, state.as_slice()).unwrap();
+    assert!(restored.is_background(&wall));
+}
+
+#[test]
+fn threshold_test() {
+    let filter = common::load(|_| {});
+    let wall = Point3::new(2.0, 0.0, 0.0);
+
+    // The wall is observed in half of the frames.
+    for step in 0..10 {
+        if step % 2 == 0 {
+            filter.check_is_background(&wall);
+        }
+        filter.step();
+    }
+    assert!(!filter.is_background(&wall));
+
+    // A lower threshold applies to the learned statistics.
+    let mut config = filter.config().clone();
+    config.background_threshold = r64(0.4);
+    let migrated = filter.migrate(&config).unwrap();
+    assert!(migrated.is_background(&wall));
+}
+
+#[test]
+fn memory_stats_test() {
+    let filter = common::load(|config| {
+        config.max_voxels = NonZeroUsize::new(4);
+    });
+    let wall = Point3::new(2.0, 0.0, 0.0);
+
+    for x in -4..=1 {
+        filter.check_is_background(&wall);
+        filter.check_is_background(&Point3::new(x as f64, 0.0, 0.0));
+        filter.step();
+    }
+    let stats = filter.memory_stats();
+    assert!(stats.evicted > 0);
+
+    // The counters are carried over.
+    let mut config = filter.config().clone();
+    config.background_threshold = r64(0.4);
+    let migrated = filter.migrate(&config).unwrap();
+    assert_eq!(migrated.memory_stats().evicted, stats.evicted);
+    assert_eq!(migrated.memory_stats().refused, stats.refused);
+}
+
+fn shift(range: &RangeInclusive<R64>, offset: f64) -> RangeInclusive<R64> {
+    (*range.start() + offset)..=(*range.end() + offset)
+}
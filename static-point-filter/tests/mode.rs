@@ -0,0 +1,63 @@
+use nalgebra::Point3;
+use static_point_filter::{Mode, StaticPointFilter};
+use std::fs;
+
+#[test]
+fn learn_then_freeze_test() {
+    let text = fs::read_to_string("tests/simple.json").unwrap();
+    let filter: StaticPointFilter = serde_json::from_str(&text).unwrap();
+    assert_eq!(filter.mode(), Mode::Adaptive);
+
+    let wall = Point3::new(2.0, 0.0, 0.0);
+    let car = Point3::new(-2.0, 0.0, 0.0);
+
+    // All points are background while learning.
+    filter.set_mode(Mode::Learning);
+    for _ in 0..10 {
+        assert!(filter.check_is_background(&wall));
+        filter.step();
+    }
+    assert!(filter.is_background(&car));
+
+    // A slow queue of cars is never absorbed once frozen.
+    filter.set_mode(Mode::Frozen);
+    for _ in 0..100 {
+        assert!(filter.check_is_background(&wall));
+        assert!(!filter.check_is_background(&car));
+        filter.step();
+    }
+
+    // The queue is absorbed in the adaptive mode.
+    filter.set_mode(Mode::Adaptive);
+    for _ in 0..100 {
+        filter.check_is_background(&car);
+        filter.step();
+    }
+    assert!(filter.is_background(&car));
+}
+
+#[test]
+fn frozen_state_test() {
+    let text = fs::read_to_string("tests/simple.json").unwrap();
+    let filter: StaticPointFilter = serde_json::from_str(&text).unwrap();
+    let wall = Point3::new(2.0, 0.0, 0.0);
+    for _ in 0..10 {
+        filter.check_is_background(&wall);
+        filter.step();
+    }
+    let mut snapshot = vec![];
+    filter.save_state(&mut snapshot).unwrap();
+
+    // Start frozen from the config, and restore the background.
+    let mut config = filter.config().clone();
+    config.mode = Mode::Frozen;
+    let frozen = config.build();
+    assert_eq!(frozen.mode(), Mode::Frozen);
+    frozen.load_state(snapshot.as_slice()).unwrap();
+    assert!(frozen.check_is_background(&wall));
+    assert!(!frozen.check_is_background(&Point3::new(-2.0, 0.0, 0.0)));
+
+    let cloned = frozen.clone();
+    assert_eq!(cloned.mode(), Mode::Frozen);
+    assert!(cloned.is_background(&wall));
+}
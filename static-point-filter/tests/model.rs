@@ -0,0 +1,175 @@
+mod common;
+
+use nalgebra::Point3;
+use static_point_filter::{Model, StaticPointFilter};
+use std::num::NonZeroUsize;
+
+fn sliding_window(window: usize) -> Model {
+    Model::SlidingWindow {
+        window: NonZeroUsize::new(window).unwrap(),
+    }
+}
+
+/// Observes the car for some frames, and then the empty spot after
+/// the car leaves. Returns whether the spot is still background in
+/// the end.
+fn car_leaves(filter: &StaticPointFilter, parked: usize, left: usize) -> bool {
+    let car = Point3::new(2.0, 0.0, 0.0);
+    let wall = Point3::new(-2.0, 0.0, 0.0);
+
+    for _ in 0..parked {
+        filter.check_is_background(&car);
+        filter.check_is_background(&wall);
+        filter.step();
+    }
+    assert!(filter.is_background(&car));
+
+    for _ in 0..left {
+        filter.check_is_background(&wall);
+        filter.step();
+    }
+    filter.is_background(&car)
+}
+
+#[test]
+fn cumulative_test() {
+    let filter = common::load(|config| config.model = Model::Cumulative);
+    assert!(car_leaves(&filter, 100, 10));
+}
+
+#[test]
+fn sliding_window_test() {
+    let filter = common::load(|config| config.model = sliding_window(20));
+    assert!(!car_leaves(&filter, 100, 10));
+    assert!(filter.is_background(&Point3::new(-2.0, 0.0, 0.0)));
+}
+
+#[test]
+fn long_sliding_window_test() {
+    let filter = common::load(|config| config.model = sliding_window(200));
+    assert!(!car_leaves(&filter, 300, 50));
+
+    let filter = common::load(|config| config.model = sliding_window(200));
+    assert!(car_leaves(&filter, 300, 30));
+}
+
+/// Compares the sliding windows with the occupancy counted over the
+/// exact last frames.
+#[test]
+fn sliding_window_exact_test() {
+    let point = Point3::new(2.0, 0.0, 0.0);
+
+    for window in [20, 64, 65, 100, 128, 300] {
+        let filter = common::load(|config| config.model = sliding_window(window));
+        let mut history = vec![];
+
+        for step in 0..1000usize {
+            // Occupied in bursts of varying lengths.
+            let occupied = (step * 7 / 11) % 5 != 0 && step % 97 > 30;
+            if occupied {
+                filter.check_is_background(&point);
+            }
+            history.push(occupied);
+
+            let start = (step + 1).saturating_sub(window);
+            let occupancy = history[start..=step]
+                .iter()
+                .filter(|&&occupied| occupied)
+                .count();
+            let threshold = (step.min(window) as f64 * 0.8).ceil() as usize;
+            assert_eq!(
+                filter.is_background(&point),
+                occupancy >= threshold,
+                "window {window} at step {step}"
+            );
+
+            filter.step();
+        }
+    }
+}
+
+#[test]
+fn exponential_decay_test() {
+    let filter = common::load(|config| {
+        config.model = Model::ExponentialDecay {
+            half_life: 10.0.try_into().unwrap(),
+        }
+    });
+    assert!(!car_leaves(&filter, 100, 10));
+    assert!(filter.is_background(&Point3::new(-2.0, 0.0, 0.0)));
+}
+
+#[test]
+fn periodic_reset_test() {
+    let filter = common::load(|config| {
+        config.model = Model::PeriodicReset {
+            period: NonZeroUsize::new(50).unwrap(),
+        }
+    });
+    assert!(!car_leaves(&filter, 90, 20));
+}
+
+#[test]
+fn model_config_test() {
+    let parse = |model: &str| {
+        let text = format!(
+            r#"{{
+                "range": {{
+                    "x_bound": [-4.5, 4.5],
+                    "y_bound": [-4.5, 4.5],
+                    "z_bound": [-4.5, 4.5]
+                }},
+                "voxel_size": {{ "x_size": 1.0, "y_size": 1.0, "z_size": 1.0 }},
+                "background_threshold": 0.8,
+                "model": {model}
+            }}"#
+        );
+        serde_json::from_str::<StaticPointFilter>(&text).map(|filter| filter.config().model)
+    };
+
+    assert_eq!(
+        parse(r#"{ "type": "sliding_window", "window": 32 }"#).unwrap(),
+        sliding_window(32)
+    );
+    assert_eq!(
+        parse(r#"{ "type": "sliding_window", "window": 1000 }"#).unwrap(),
+        sliding_window(1000)
+    );
+    assert!(parse(r#"{ "type": "sliding_window", "window": 0 }"#).is_err());
+    assert!(parse(r#"{ "type": "sliding_window", "window": 65537 }"#).is_err());
+    assert!(parse(r#"{ "type": "exponential_decay", "half_life": 0 }"#).is_err());
+    assert!(parse(r#"{ "type": "periodic_reset", "period": 0 }"#).is_err());
+}
+
+#[test]
+fn model_state_test() {
+    let filter = common::load(|config| config.model = sliding_window(20));
+    let mut snapshot = vec![];
+    filter.save_state(&mut snapshot).unwrap();
+
+    assert!(StaticPointFilter::from_state(filter.config(), snapshot.as_slice()).is_ok());
+    let other = common::load(|config| config.model = Model::Cumulative);
+    assert!(other.load_state(snapshot.as_slice()).is_err());
+}
+
+#[test]
+fn long_window_state_test() {
+    let filter = common::load(|config| config.model = sliding_window(100));
+    let car = Point3::new(2.0, 0.0, 0.0);
+    for _ in 0..150 {
+        filter.check_is_background(&car);
+        filter.step();
+    }
+
+    let mut snapshot = vec![];
+    filter.save_state(&mut snapshot).unwrap();
+    let restored = StaticPointFilter::from_state(filter.config(), snapshot.as_slice()).unwrap();
+
+    // The car leaves, and both filters forget it at the same frame.
+    for _ in 0..100 {
+        assert_eq!(filter.is_background(&car), restored.is_background(&car));
+        filter.step();
+        restored.step();
+    }
+    assert!(!restored.is_background(&car));
+}
@@ -0,0 +1,161 @@
+mod common;
+
+use nalgebra::Point3;
+use noisy_float::prelude::*;
+use static_point_filter::{Aggregation, Config, Mode, Neighborhood, StaticPointFilter};
+
+/// Observes each point in the given number of the first 10 frames.
+fn learn(filter: &StaticPointFilter, points: &[(Point3<f64>, usize)]) {
+    for frame in 0..10 {
+        for &(point, frames) in points {
+            if frame < frames {
+                filter.check_is_background(&point);
+            }
+        }
+        filter.step();
+    }
+}
+
+#[test]
+fn boundary_flicker_test() {
+    // The wall is at the center of a voxel, and some returns jitter
+    // into the next voxel every 5 frames.
+    let wall = Point3::new(0.0, 0.0, 0.0);
+    let jitter = Point3::new(0.55, 0.0, 0.0);
+
+    let run = |filter: &StaticPointFilter| {
+        let mut all_background = true;
+        for frame in 0..50 {
+            filter.check_is_background(&wall);
+            if frame % 5 == 0 {
+                all_background &= filter.check_is_background(&jitter);
+            }
+            filter.step();
+        }
+        all_background
+    };
+
+    let filter = common::load(|config| config.neighborhood = Neighborhood::Single);
+    assert!(!run(&filter));
+
+    let filter = common::load(|config| {
+        config.neighborhood = Neighborhood::Adjacent {
+            connectivity: 6,
+            aggregation: Aggregation::Max,
+            axis_weights: [r64(1.0); 3],
+        }
+    });
+    assert!(run(&filter));
+}
+
+#[test]
+fn adjacent_max_test() {
+    let filter = common::load(|config| {
+        config.neighborhood = Neighborhood::Adjacent {
+            connectivity: 6,
+            aggregation: Aggregation::Max,
+            axis_weights: [r64(1.0); 3],
+        }
+    });
+    let wall = Point3::new(0.0, 0.0, 0.0);
+    let jitter = Point3::new(0.55, 0.0, 0.0);
+    learn(&filter, &[(wall, 10), (jitter, 2)]);
+
+    assert!(filter.is_background(&jitter));
+    assert!(!filter.is_background(&Point3::new(-2.0, 0.0, 0.0)));
+
+    // The export aggregates the neighbors as well.
+    let threshold = filter.config().background_threshold.raw();
+    let centers: Vec<_> = filter
+        .export_background(threshold)
+        .into_iter()
+        .map(|voxel| voxel.center)
+        .collect();
+    assert_eq!(centers, vec![wall, Point3::new(1.0, 0.0, 0.0)]);
+
+    // The neighbors are also counted when frozen.
+    filter.set_mode(Mode::Frozen);
+    assert!(filter.is_background(&jitter));
+    assert!(!filter.is_background(&Point3::new(-2.0, 0.0, 0.0)));
+}
+
+#[test]
+fn adjacent_weighted_sum_test() {
+    // The voxel between two walls is occupied in 6 of 10 frames.
+    let points = [
+        (Point3::new(0.0, 0.0, 0.0), 10),
+        (Point3::new(1.0, 0.0, 0.0), 6),
+        (Point3::new(2.0, 0.0, 0.0), 10),
+    ];
+    let point = Point3::new(1.0, 0.0, 0.0);
+
+    let filter = common::load(|config| config.neighborhood = Neighborhood::Single);
+    learn(&filter, &points);
+    assert!(!filter.is_background(&point));
+
+    // Neighbors along the y and z axes are empty.
+    let filter = common::load(|config| {
+        config.neighborhood = Neighborhood::Adjacent {
+            connectivity: 6,
+            aggregation: Aggregation::WeightedSum,
+            axis_weights: [r64(1.0); 3],
+        }
+    });
+    learn(&filter, &points);
+    assert!(!filter.is_background(&point));
+
+    let filter = common::load(|config| {
+        config.neighborhood = Neighborhood::Adjacent {
+            connectivity: 26,
+            aggregation: Aggregation::WeightedSum,
+            axis_weights: [r64(1.0), r64(0.0), r64(0.0)],
+        }
+    });
+    learn(&filter, &points);
+    assert!(filter.is_background(&point));
+}
+
+#[test]
+fn trilinear_test() {
+    let filter = common::load(|config| {
+        config.neighborhood = Neighborhood::Trilinear { axes: [true; 3] };
+    });
+    learn(
+        &filter,
+        &[
+            (Point3::new(0.0, 0.0, 0.0), 10),
+            (Point3::new(1.0, 0.0, 0.0), 7),
+        ],
+    );
+
+    // Points near the boundary to the wall voxel are background.
+    assert!(filter.is_background(&Point3::new(0.55, 0.0, 0.0)));
+    assert!(!filter.is_background(&Point3::new(0.95, 0.0, 0.0)));
+    assert!(filter.is_background(&Point3::new(0.0, 0.0, 0.0)));
+}
+
+#[test]
+fn invalid_connectivity_test() {
+    let text = r#"{
+        "range": {
+            "x_bound": [-4.5, 4.5],
+            "y_bound": [-4.5, 4.5],
+            "z_bound": [-4.5, 4.5]
+        },
+        "voxel_size": { "x_size": 1.0, "y_size": 1.0, "z_size": 1.0 },
+        "background_threshold": 0.8,
+        "neighborhood": { "type": "adjacent", "connectivity": 7, "aggregation": "max" }
+    }"#;
+    assert!(serde_json::from_str::<Config>(text).is_err());
+
+    let text = text.replace("7", "18");
+    let config: Config = serde_json::from_str(&text).unwrap();
+    assert_eq!(
+        config.neighborhood,
+        Neighborhood::Adjacent {
+            connectivity: 18,
+            aggregation: Aggregation::Max,
+            axis_weights: [r64(1.0); 3],
+        }
+    );
+}
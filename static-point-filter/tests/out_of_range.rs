@@ -0,0 +1,66 @@
+mod common;
+
+use nalgebra::Point3;
+use static_point_filter::{OutOfRange, StaticPointFilter};
+
+#[test]
+fn out_of_range_test() {
+    let far = Point3::new(-20.0, 0.0, 0.0);
+    let nan = Point3::new(f64::NAN, 0.0, 0.0);
+
+    // Out-of-range points are background by default.
+    let filter = common::load(|config| config.out_of_range = OutOfRange::default());
+    assert!(filter.check_is_background(&far));
+    assert!(filter.check_is_background(&nan));
+
+    let filter = common::load(|config| config.out_of_range = OutOfRange::Keep);
+    assert!(!filter.check_is_background(&far));
+    assert!(filter.check_is_background(&nan));
+
+    // Voxels below the lower bound are learned like any other.
+    let filter = common::load(|config| config.out_of_range = OutOfRange::Ignore);
+    let wall = Point3::new(-20.0, -30.0, -0.5);
+    let person = Point3::new(-22.0, -30.0, -0.5);
+    for _ in 0..10 {
+        filter.check_is_background(&wall);
+        filter.step();
+    }
+    assert!(filter.is_background(&wall));
+    assert!(!filter.is_background(&person));
+    assert!(filter.check_is_background(&nan));
+
+    // The snapshot keeps negative voxel indices.
+    let mut snapshot = vec![];
+    filter.save_state(&mut snapshot).unwrap();
+    let restored = StaticPointFilter::from_state(filter.config(), snapshot.as_slice()).unwrap();
+    assert!(restored.is_background(&wall));
+    assert!(!restored.is_background(&person));
+
+    // Voxel indices beyond 32 bits are saved as well.
+    let far = Point3::new(1e12, 0.0, 0.0);
+    for _ in 0..100 {
+        filter.check_is_background(&far);
+        filter.step();
+    }
+    let mut snapshot = vec![];
+    filter.save_state(&mut snapshot).unwrap();
+    let restored = StaticPointFilter::from_state(filter.config(), snapshot.as_slice()).unwrap();
+    assert!(restored.is_background(&far));
+    assert!(!restored.is_background(&Point3::new(-1e12, 0.0, 0.0)));
+}
+
+#[test]
+fn out_of_range_config_test() {
+    let text = r#"{
+        "range": {
+            "x_bound": [-4.5, 4.5],
+            "y_bound": [-4.5, 4.5],
+            "z_bound": [-4.5, 4.5]
+        },
+        "voxel_size": { "x_size": 1.0, "y_size": 1.0, "z_size": 1.0 },
+        "background_threshold": 0.8,
+        "out_of_range": "ignore"
+    }"#;
+    let filter: StaticPointFilter = serde_json::from_str(text).unwrap();
+    assert_eq!(filter.config().out_of_range, OutOfRange::Ignore);
+}
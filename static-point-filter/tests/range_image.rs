@@ -0,0 +1,30 @@
+use nalgebra::Point3;
+use static_point_filter::RangeImageFilter;
+use std::fs;
+
+#[test]
+fn range_image_test() {
+    let text = fs::read_to_string("tests/range_image.json").unwrap();
+    let filter: RangeImageFilter = serde_json::from_str(&text).unwrap();
+
+    // A wall 20 meters ahead of the sensor at 1 meter high.
+    let wall = Point3::new(20.0, 0.0, 1.0);
+    for _ in 0..10 {
+        filter.check_is_background(&wall);
+        filter.step();
+    }
+    assert!(filter.is_background(&wall));
+
+    // A person in front of the wall in the same beam direction.
+    assert!(!filter.is_background(&Point3::new(10.0, 0.0, 1.0)));
+
+    // Points within the margin or behind the wall are background.
+    assert!(filter.is_background(&Point3::new(19.2, 0.0, 1.0)));
+    assert!(filter.is_background(&Point3::new(30.0, 0.0, 1.0)));
+
+    // The free space in other directions has no background surface.
+    assert!(!filter.is_background(&Point3::new(0.0, 10.0, 1.0)));
+
+    // Points out of the elevation bound are background.
+    assert!(filter.is_background(&Point3::new(1.0, 0.0, 10.0)));
+}
@@ -32,3 +32,30 @@ fn simple_filter_test() {
     assert!(!filter.check_is_background(&point2));
     assert!(filter.check_is_background(&point3));
 }
+
+#[test]
+fn predict_check_test() {
+    let config = fs::read_to_string("tests/simple.json").unwrap();
+    let filter: StaticPointFilter = serde_json::from_str(&config).unwrap();
+    let points = [
+        Point3::origin(),
+        Point3::new(1.0, 0.0, 0.0),
+        Point3::new(-2.0, 1.0, 0.0),
+    ];
+
+    for step in 0..100 {
+        for (index, point) in points.iter().enumerate() {
+            // Each point is observed in a different pattern of frames.
+            if (step + index) % (index + 2) == 0 {
+                continue;
+            }
+            let expect = filter.is_background(point);
+            assert_eq!(filter.check_is_background(point), expect, "step {step}");
+            assert_eq!(
+                filter.is_background(point),
+                filter.check_is_background(point)
+            );
+        }
+        filter.step();
+    }
+}
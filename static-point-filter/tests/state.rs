@@ -0,0 +1,100 @@
+use nalgebra::Point3;
+use static_point_filter::StaticPointFilter;
+use std::fs;
+
+fn learn() -> StaticPointFilter {
+    let text = fs::read_to_string("tests/simple.json").unwrap();
+    let filter: StaticPointFilter = serde_json::from_str(&text).unwrap();
+    let wall = Point3::new(2.0, 0.0, 0.0);
+
+    for _ in 0..70 {
+        filter.check_is_background(&wall);
+        filter.step();
+    }
+    filter
+}
+
+#[test]
+fn save_load_state_test() {
+    let filter = learn();
+    let wall = Point3::new(2.0, 0.0, 0.0);
+    let person = Point3::new(-2.0, 0.0, 0.0);
+    assert!(filter.is_background(&wall));
+    assert!(!filter.is_background(&person));
+
+    let mut snapshot = vec![];
+    filter.save_state(&mut snapshot).unwrap();
+
+    // Start a new filter from the snapshot.
+    let restored = StaticPointFilter::from_state(filter.config(), snapshot.as_slice()).unwrap();
+    assert!(restored.is_background(&wall));
+    assert!(!restored.is_background(&person));
+
+    // Load the snapshot into a fresh filter.
+    let text = fs::read_to_string("tests/simple.json").unwrap();
+    let fresh: StaticPointFilter = serde_json::from_str(&text).unwrap();
+    fresh.load_state(snapshot.as_slice()).unwrap();
+    assert!(fresh.is_background(&wall));
+    assert!(!fresh.is_background(&person));
+
+    let mut resaved = vec![];
+    fresh.save_state(&mut resaved).unwrap();
+    assert_eq!(resaved.len(), snapshot.len());
+}
+
+#[test]
+fn invalid_state_test() {
+    let filter = learn();
+    let mut snapshot = vec![];
+    filter.save_state(&mut snapshot).unwrap();
+
+    // Corrupted data
+    let mut corrupted = snapshot.clone();
+    corrupted[20] ^= 1;
+    assert!(filter.load_state(corrupted.as_slice()).is_err());
+
+    // Truncated data
+    assert!(filter.load_state(&snapshot[..snapshot.len() - 1]).is_err());
+
+    // Incompatible config
+    let mut config = filter.config().clone();
+    config.voxel_size.x_size *= 2.0;
+    assert!(StaticPointFilter::from_state(&config, snapshot.as_slice()).is_err());
+
+    // A different threshold is compatible.
+    let mut config = filter.config().clone();
+    config.background_threshold /= 2.0;
+    assert!(StaticPointFilter::from_state(&config, snapshot.as_slice()).is_ok());
+}
+
+#[test]
+fn version_4_state_test() {
+    let filter = learn();
+    let wall = Point3::new(2.0, 0.0, 0.0);
+    let mut snapshot = vec![];
+    filter.save_state(&mut snapshot).unwrap();
+
+    // Rewrite the snapshot in version 4, which has no hysteresis
+    // config and voxel states.
+    const HEADER: usize = 4 + 4 + 9 * 8 + 12;
+    const HYSTERESIS: usize = 5 * 8;
+    const VOXEL: usize = 3 * 8 + 3 * 8;
+    let body = &snapshot[..snapshot.len() - 4];
+    let mut old = vec![];
+    old.extend(&body[..4]);
+    old.extend(4u32.to_le_bytes());
+    old.extend(&body[8..HEADER]);
+    old.extend(&body[HEADER + HYSTERESIS..HEADER + HYSTERESIS + 24]);
+    for voxel in body[HEADER + HYSTERESIS + 24..].chunks(VOXEL) {
+        old.extend(&voxel[..VOXEL - 8]);
+    }
+    old.extend(crc32fast::hash(&old).to_le_bytes());
+
+    let restored = StaticPointFilter::from_state(filter.config(), old.as_slice()).unwrap();
+    assert!(restored.is_background(&wall));
+    assert!(!restored.is_background(&Point3::new(-2.0, 0.0, 0.0)));
+
+    let mut resaved = vec![];
+    restored.save_state(&mut resaved).unwrap();
+    assert_eq!(resaved, snapshot);
+}